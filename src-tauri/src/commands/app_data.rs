@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::error::{CommandError, ErrorKind};
+
+// Set once in setup from Tauri's path resolver
+lazy_static::lazy_static! {
+    static ref APP_DATA_DIR: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+}
+
+/// Sets where `app_data_dir` points, from `app.path().app_data_dir()` in setup.
+pub fn set_app_data_dir(dir: PathBuf) {
+    *APP_DATA_DIR.lock().unwrap() = Some(dir);
+}
+
+/// Per-user directory for data the app keeps between runs: local history,
+/// buffer backups and the folders picked as workspace roots.
+pub fn app_data_dir() -> Result<PathBuf, CommandError> {
+    APP_DATA_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| CommandError::new(ErrorKind::NotFound, "Could not determine app data directory"))
+}
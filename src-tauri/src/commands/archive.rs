@@ -0,0 +1,319 @@
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::encoding;
+use super::filesystem::{compare_entries, FileEntry};
+use super::text_format::{detect_line_ending, LineEnding};
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+/// Separates the archive file from the member inside it in a virtual path,
+/// e.g. `/work/release.tar.gz!/bin/tool`, as in jar URLs.
+pub const ARCHIVE_SEPARATOR: &str = "!/";
+
+// Members bigger than this aren't opened as text, like files on disk
+const MAX_MEMBER_SIZE: u64 = 50 * 1024 * 1024;
+
+// Archives whose member lists are kept; the cache starts over past this
+const MAX_CACHED_ARCHIVES: usize = 16;
+
+// Member lists by archive path, so expanding folders of a big tarball doesn't
+// decompress it every time. Entries are dropped once the file changes.
+lazy_static::lazy_static! {
+    static ref ARCHIVE_INDEXES: Arc<Mutex<HashMap<PathBuf, ArchiveIndex>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+#[derive(Clone)]
+struct Member {
+    name: String, // Normalized, without leading or trailing slashes
+    size: u64,
+    is_directory: bool,
+    is_symlink: bool,
+}
+
+#[derive(Clone)]
+struct ArchiveIndex {
+    modified: Option<SystemTime>,
+    len: u64,
+    members: Vec<Member>,
+}
+
+/// A member of an archive read as text. Members are read-only.
+#[derive(Serialize)]
+pub struct ArchiveFile {
+    pub content: String,
+    pub encoding: String,
+    pub has_bom: bool,
+    pub line_ending: Option<LineEnding>,
+    pub size: u64,
+}
+
+/// Splits a virtual path into the archive file and the member path inside
+/// it, which is empty for the archive's root.
+pub fn split_archive_path(path: &str) -> (&str, &str) {
+    match path.split_once(ARCHIVE_SEPARATOR) {
+        Some((archive, member)) => (archive, member.trim_matches('/')),
+        None => (path, ""),
+    }
+}
+
+// Drops "." and empty components and refuses "..", so member names can't
+// point outside the archive
+fn normalize_member_name(name: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn detect_format(path: &Path) -> Result<ArchiveFormat, CommandError> {
+    let mut head = [0u8; 512];
+    let mut file = File::open(path).map_err(|e| CommandError::io("open archive", e).with_path(path))?;
+    let read = file.read(&mut head).map_err(|e| CommandError::io("read archive", e).with_path(path))?;
+    let head = &head[..read];
+
+    if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        Ok(ArchiveFormat::Zip)
+    } else if head.starts_with(b"\x1f\x8b") {
+        Ok(ArchiveFormat::TarGz)
+    } else if head.get(257..262) == Some(b"ustar") {
+        Ok(ArchiveFormat::Tar)
+    } else {
+        let message = format!("Not a supported archive: {}", path.display());
+        Err(CommandError::invalid_input(message).with_path(path))
+    }
+}
+
+fn zip_error(path: &Path, e: zip::result::ZipError) -> CommandError {
+    match e {
+        zip::result::ZipError::Io(e) => CommandError::io("read archive", e).with_path(path),
+        e => CommandError::invalid_input(format!("Invalid zip archive: {}", e)).with_path(path),
+    }
+}
+
+fn open_zip(path: &Path) -> Result<zip::ZipArchive<BufReader<File>>, CommandError> {
+    let file = File::open(path).map_err(|e| CommandError::io("open archive", e).with_path(path))?;
+    zip::ZipArchive::new(BufReader::new(file)).map_err(|e| zip_error(path, e))
+}
+
+fn open_tar(path: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>, CommandError> {
+    let file = File::open(path).map_err(|e| CommandError::io("open archive", e).with_path(path))?;
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(BufReader::new(file))),
+        _ => Box::new(BufReader::new(file)),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+fn read_error(path: &Path, e: std::io::Error) -> CommandError {
+    let mut error = CommandError::io("read archive", e).with_path(path);
+    // Corrupt or truncated archives surface as I/O errors
+    if error.kind == ErrorKind::Io || error.kind == ErrorKind::NotUtf8 {
+        error.kind = ErrorKind::InvalidInput;
+    }
+    error
+}
+
+fn read_members(path: &Path, format: ArchiveFormat) -> Result<Vec<Member>, CommandError> {
+    let mut members = Vec::new();
+
+    if format == ArchiveFormat::Zip {
+        let mut archive = open_zip(path)?;
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i).map_err(|e| zip_error(path, e))?;
+            if let Some(name) = normalize_member_name(file.name()) {
+                members.push(Member {
+                    name,
+                    size: file.size(),
+                    is_directory: file.is_dir(),
+                    is_symlink: file.is_symlink(),
+                });
+            }
+        }
+        return Ok(members);
+    }
+
+    let mut archive = open_tar(path, format)?;
+    for entry in archive.entries().map_err(|e| read_error(path, e))? {
+        let entry = entry.map_err(|e| read_error(path, e))?;
+        let header = entry.header();
+        let name = entry.path().map_err(|e| read_error(path, e))?;
+        if let Some(name) = normalize_member_name(&name.to_string_lossy()) {
+            members.push(Member {
+                name,
+                size: header.size().unwrap_or(0),
+                is_directory: header.entry_type().is_dir(),
+                is_symlink: header.entry_type().is_symlink() || header.entry_type().is_hard_link(),
+            });
+        }
+    }
+
+    Ok(members)
+}
+
+fn archive_index(path: &Path) -> Result<ArchiveIndex, CommandError> {
+    let metadata = fs::metadata(path).map_err(|e| CommandError::io("get file metadata", e).with_path(path))?;
+    let modified = metadata.modified().ok();
+
+    if let Some(index) = ARCHIVE_INDEXES.lock().unwrap().get(path) {
+        if index.modified == modified && index.len == metadata.len() {
+            return Ok(index.clone());
+        }
+    }
+
+    let index = ArchiveIndex {
+        modified,
+        len: metadata.len(),
+        members: read_members(path, detect_format(path)?)?,
+    };
+
+    let mut indexes = ARCHIVE_INDEXES.lock().unwrap();
+    if indexes.len() >= MAX_CACHED_ARCHIVES {
+        indexes.clear();
+    }
+    indexes.insert(path.to_path_buf(), index.clone());
+
+    Ok(index)
+}
+
+/// Lists one folder inside an archive as if it were a directory. `path` is
+/// the archive itself for its root, or a virtual path to a folder in it.
+/// Folders that only exist as a prefix of member names are listed too.
+#[tauri::command(async)]
+pub fn read_archive_directory(path: String) -> Result<Vec<FileEntry>, CommandError> {
+    let (archive_path, folder) = split_archive_path(&path);
+    let resolved = workspace::check_path(archive_path)?;
+    let index = archive_index(&resolved)?;
+    let prefix = if folder.is_empty() { String::new() } else { format!("{}/", folder) };
+
+    let mut children: BTreeMap<String, FileEntry> = BTreeMap::new();
+    for member in &index.members {
+        let Some(rest) = member.name.strip_prefix(&prefix) else {
+            continue;
+        };
+        if rest.is_empty() {
+            continue;
+        }
+
+        let (name, nested) = match rest.split_once('/') {
+            Some((name, _)) => (name, true),
+            None => (rest, false),
+        };
+        let entry = FileEntry {
+            name: name.to_string(),
+            path: format!("{}{}{}{}", archive_path, ARCHIVE_SEPARATOR, prefix, name),
+            is_directory: nested || member.is_directory,
+            is_symlink: !nested && member.is_symlink,
+            size: if nested { 0 } else { member.size },
+            is_ignored: false,
+        };
+
+        // An explicit member wins over a folder implied by a deeper member
+        if !nested || !children.contains_key(name) {
+            children.insert(name.to_string(), entry);
+        }
+    }
+
+    if !folder.is_empty() && children.is_empty() && !index.members.iter().any(|m| m.name == folder) {
+        return Err(CommandError::not_found(format!("Folder not found in archive: {}", path), archive_path));
+    }
+
+    let mut entries: Vec<FileEntry> = children.into_values().collect();
+    entries.sort_by(|a, b| compare_entries(a.is_directory, &a.name, b.is_directory, &b.name));
+
+    Ok(entries)
+}
+
+// Reads `member`, skipping the first `skip` members with the same name
+fn read_member(path: &Path, member: &Member, skip: usize) -> Result<Vec<u8>, CommandError> {
+    let format = detect_format(path)?;
+    let mut bytes = Vec::with_capacity(member.size as usize);
+    let mut skip = skip;
+
+    if format == ArchiveFormat::Zip {
+        let mut archive = open_zip(path)?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).map_err(|e| zip_error(path, e))?;
+            if normalize_member_name(file.name()).as_deref() == Some(&member.name) {
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                file.read_to_end(&mut bytes).map_err(|e| read_error(path, e))?;
+                return Ok(bytes);
+            }
+        }
+    } else {
+        let mut archive = open_tar(path, format)?;
+        for entry in archive.entries().map_err(|e| read_error(path, e))? {
+            let mut entry = entry.map_err(|e| read_error(path, e))?;
+            let name = entry.path().map_err(|e| read_error(path, e))?;
+            if normalize_member_name(&name.to_string_lossy()).as_deref() == Some(&member.name) {
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                entry.read_to_end(&mut bytes).map_err(|e| read_error(path, e))?;
+                return Ok(bytes);
+            }
+        }
+    }
+
+    Err(CommandError::not_found(format!("File not found in archive: {}", member.name), path))
+}
+
+/// Reads a file inside an archive as text, given its virtual path.
+#[tauri::command(async)]
+pub fn read_archive_file(path: String) -> Result<ArchiveFile, CommandError> {
+    let (archive_path, member_name) = split_archive_path(&path);
+    let resolved = workspace::check_path(archive_path)?;
+    let index = archive_index(&resolved)?;
+
+    // A name can appear more than once, as when a tarball is appended to.
+    // The last one wins, as it does when the archive is extracted.
+    let position = index
+        .members
+        .iter()
+        .rposition(|m| m.name == member_name)
+        .ok_or_else(|| CommandError::not_found(format!("File not found in archive: {}", path), archive_path))?;
+    let member = &index.members[position];
+    let earlier = index.members[..position].iter().filter(|m| m.name == member_name).count();
+
+    if member.is_directory || member.is_symlink {
+        let message = format!("Not a regular file in the archive: {}", path);
+        return Err(CommandError::invalid_input(message).with_path(archive_path));
+    }
+    if member.size > MAX_MEMBER_SIZE {
+        let message = "File in archive is too large (>50MB)";
+        return Err(CommandError::new(ErrorKind::TooLarge, message).with_path(archive_path));
+    }
+
+    let bytes = read_member(&resolved, member, earlier)?;
+    let decoded = encoding::decode(&bytes).map_err(|e| e.with_path(archive_path))?;
+
+    Ok(ArchiveFile {
+        line_ending: detect_line_ending(&decoded.text),
+        content: decoded.text,
+        encoding: decoded.encoding,
+        has_bom: decoded.has_bom,
+        size: bytes.len() as u64,
+    })
+}
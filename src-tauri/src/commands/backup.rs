@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::app_data::app_data_dir;
+use super::filesystem::{atomic_write, read_text_file, TextFile};
+use super::text_format::LineEnding;
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+/// Unsaved content of an editor buffer, kept so it survives the app closing.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BufferBackup {
+    pub path: String,
+    pub content: String,
+    pub encoding: Option<String>,
+    pub has_bom: Option<bool>,
+    pub line_ending: Option<LineEnding>,
+    pub saved_at_ms: u64,
+}
+
+/// A backup offered back on startup, next to what is on disk now.
+#[derive(Serialize)]
+pub struct RecoveredBuffer {
+    #[serde(flatten)]
+    pub backup: BufferBackup,
+    pub disk: Option<TextFile>, // None if the file was deleted or can't be read as text
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn key(value: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(value.as_bytes()));
+    hash[..32].to_string()
+}
+
+// Backups are grouped per workspace, so opening another folder doesn't bring
+// back buffers that belong elsewhere
+fn workspace_dir(workspace_root: &str) -> Result<PathBuf, CommandError> {
+    let root = workspace::check_path(workspace_root)?;
+    Ok(app_data_dir()?.join("backups").join(key(&root.to_string_lossy())))
+}
+
+// Keyed by the resolved path, so every spelling of a file shares one backup
+fn backup_path(dir: &Path, resolved: &Path) -> PathBuf {
+    dir.join(format!("{}.json", key(&resolved.to_string_lossy())))
+}
+
+fn remove_backup(file: &Path) -> Result<(), CommandError> {
+    match fs::remove_file(file) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(CommandError::io("remove backup", e).with_path(file))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Cleanup while listing is best effort; one stuck file shouldn't hide the rest
+fn remove_stale_backup(file: &Path) {
+    if let Err(e) = remove_backup(file) {
+        log::warn!("Failed to remove stale backup {}: {}", file.display(), e);
+    }
+}
+
+/// Stores the current content of a dirty buffer, replacing its previous backup.
+#[tauri::command]
+pub fn backup_buffer(
+    workspace_root: String,
+    path: String,
+    content: String,
+    encoding: Option<String>,
+    has_bom: Option<bool>,
+    line_ending: Option<LineEnding>,
+) -> Result<(), CommandError> {
+    let resolved = workspace::check_path(&path)?;
+    let dir = workspace_dir(&workspace_root)?;
+    fs::create_dir_all(&dir).map_err(|e| CommandError::io("create backup directory", e).with_path(&dir))?;
+
+    let backup = BufferBackup {
+        path: path.clone(),
+        content,
+        encoding,
+        has_bom,
+        line_ending,
+        saved_at_ms: now_ms(),
+    };
+    let json = serde_json::to_vec(&backup)
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to serialize backup: {}", e)))?;
+
+    atomic_write(&backup_path(&dir, &resolved), &json)
+}
+
+/// Returns the buffers backed up for a workspace, oldest first, each with the
+/// file as it is on disk now. Backups that no longer differ from the file are
+/// dropped instead of being returned.
+#[tauri::command]
+pub fn list_buffer_backups(workspace_root: String) -> Result<Vec<RecoveredBuffer>, CommandError> {
+    let dir = workspace_dir(&workspace_root)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CommandError::io("read backup directory", e).with_path(&dir)),
+    };
+
+    let mut recovered = Vec::new();
+    for entry in entries.flatten() {
+        let file = entry.path();
+        if file.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        // A corrupt backup can't be offered back, so it isn't worth keeping
+        let backup: BufferBackup = match fs::read(&file).ok().and_then(|b| serde_json::from_slice(&b).ok()) {
+            Some(backup) => backup,
+            None => {
+                remove_stale_backup(&file);
+                continue;
+            }
+        };
+
+        // Files outside the workspace are never read back, even if a backup names one
+        if workspace::check_path(&backup.path).is_err() {
+            continue;
+        }
+
+        let disk = read_text_file(backup.path.clone()).ok();
+        if disk.as_ref().map(|d| d.content == backup.content).unwrap_or(false) {
+            remove_stale_backup(&file);
+            continue;
+        }
+
+        recovered.push(RecoveredBuffer { backup, disk });
+    }
+
+    recovered.sort_by_key(|r| r.backup.saved_at_ms);
+    Ok(recovered)
+}
+
+/// Forgets the backup of a buffer, once it was saved, reverted or closed.
+#[tauri::command]
+pub fn discard_buffer_backup(workspace_root: String, path: String) -> Result<(), CommandError> {
+    let resolved = workspace::check_path(&path)?;
+    let dir = workspace_dir(&workspace_root)?;
+    remove_backup(&backup_path(&dir, &resolved))
+}
@@ -0,0 +1,138 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use super::filesystem::{save_file, FileFingerprint};
+use super::vfs::MAX_READ_SIZE;
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+// Largest range a single read may return, which is plenty for a hex view page
+const MAX_READ_LENGTH: u64 = 1024 * 1024;
+
+#[derive(Serialize)]
+pub struct ByteRange {
+    pub offset: u64,
+    pub data: String, // Base64
+    pub file_size: u64,
+}
+
+/// Bytes to overwrite at `offset`. Patches can't grow or shrink the file.
+#[derive(Deserialize, Clone)]
+pub struct BytePatch {
+    pub offset: u64,
+    pub data: String, // Base64
+}
+
+/// Reads up to `length` bytes at `offset`, clamped to the end of the file.
+pub fn read_range<R: Read + Seek>(
+    reader: &mut R,
+    file_size: u64,
+    offset: u64,
+    length: u64,
+) -> Result<ByteRange, CommandError> {
+    if offset > file_size {
+        let message = format!("Offset {} is past the end of the file ({} bytes)", offset, file_size);
+        return Err(CommandError::invalid_input(message));
+    }
+
+    let length = length.min(MAX_READ_LENGTH).min(file_size - offset);
+    let mut data = vec![0u8; length as usize];
+
+    reader
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| reader.read_exact(&mut data))
+        .map_err(|e| CommandError::io("read file", e))?;
+
+    Ok(ByteRange {
+        offset,
+        data: BASE64.encode(data),
+        file_size,
+    })
+}
+
+/// Writes every patch in place and returns the number of bytes changed. All
+/// patches are validated before anything is written.
+pub fn apply_patches<W: Write + Seek>(
+    writer: &mut W,
+    file_size: u64,
+    patches: &[BytePatch],
+) -> Result<u64, CommandError> {
+    let mut decoded = Vec::with_capacity(patches.len());
+
+    for patch in patches {
+        let data = BASE64.decode(&patch.data).map_err(|e| {
+            CommandError::invalid_input(format!("Invalid patch data at offset {}: {}", patch.offset, e))
+        })?;
+
+        if patch.offset.saturating_add(data.len() as u64) > file_size {
+            let message = format!("Patch at offset {} runs past the end of the file", patch.offset);
+            return Err(CommandError::invalid_input(message));
+        }
+
+        decoded.push((patch.offset, data));
+    }
+
+    let mut written = 0u64;
+    for (offset, data) in decoded {
+        writer
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| writer.write_all(&data))
+            .map_err(|e| CommandError::io("write file", e))?;
+        written += data.len() as u64;
+    }
+
+    writer.flush().map_err(|e| CommandError::io("write file", e))?;
+
+    Ok(written)
+}
+
+/// Reads raw bytes for the hex viewer, for files that can't be shown as text.
+#[tauri::command]
+pub fn read_file_bytes(path: String, offset: u64, length: u64) -> Result<ByteRange, CommandError> {
+    let file_path = workspace::check_path(&path)?;
+
+    if !file_path.is_file() {
+        let message = format!("Path is not a file: {}", path);
+        return Err(CommandError::invalid_input(message).with_path(&path));
+    }
+
+    let mut file = fs::File::open(&file_path).map_err(|e| CommandError::io("open file", e).with_path(&path))?;
+    let file_size = file
+        .metadata()
+        .map_err(|e| CommandError::io("get file metadata", e).with_path(&path))?
+        .len();
+
+    read_range(&mut file, file_size, offset, length).map_err(|e| e.with_path(&path))
+}
+
+/// Overwrites byte ranges of a file, as edited in the hex viewer, and returns
+/// the fingerprint of the patched file. The patches are applied to a copy in
+/// memory, which is then saved like any other edit: checked against
+/// `expected_fingerprint`, kept in the local history and written atomically.
+#[tauri::command]
+pub fn patch_file_bytes(
+    path: String,
+    patches: Vec<BytePatch>,
+    expected_fingerprint: Option<FileFingerprint>,
+) -> Result<FileFingerprint, CommandError> {
+    let file_path = workspace::check_path(&path)?;
+    let metadata =
+        fs::metadata(&file_path).map_err(|e| CommandError::io("get file metadata", e).with_path(&path))?;
+
+    if !metadata.is_file() {
+        return Err(CommandError::invalid_input(format!("Path is not a file: {}", path)).with_path(&path));
+    }
+    if metadata.len() > MAX_READ_SIZE {
+        let message = "File is too large to patch (>50MB)";
+        return Err(CommandError::new(ErrorKind::TooLarge, message).with_path(&path));
+    }
+
+    let mut bytes = fs::read(&file_path).map_err(|e| CommandError::io("read file", e).with_path(&path))?;
+    let file_size = bytes.len() as u64;
+    apply_patches(&mut Cursor::new(&mut bytes), file_size, &patches).map_err(|e| e.with_path(&path))?;
+
+    save_file(&file_path, &bytes, expected_fingerprint.as_ref())
+}
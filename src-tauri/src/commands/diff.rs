@@ -0,0 +1,338 @@
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, ChangeTag, DiffOp, DiffTag, TextDiff};
+use std::time::{Duration, Instant};
+
+use super::filesystem::read_text_file;
+use crate::error::CommandError;
+
+// Past this, the diff falls back to a coarser result instead of blocking on huge inputs
+const DIFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// One side of a comparison: a file read from disk, or text such as an
+/// unsaved buffer.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffSide {
+    Path(String),
+    Text(String),
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    #[default]
+    Myers,
+    Patience,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct DiffOptions {
+    pub algorithm: DiffAlgorithm,
+    /// Unchanged lines kept around each change; null returns the whole file
+    /// as a single hunk, for side-by-side views
+    pub context_lines: Option<usize>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            algorithm: DiffAlgorithm::default(),
+            context_lines: Some(DEFAULT_CONTEXT_LINES),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Context,
+    Removed,
+    Added,
+}
+
+/// Part of a line that differs from its counterpart on the other side.
+#[derive(Serialize)]
+pub struct InlineChange {
+    pub column: usize, // 1-based, in UTF-16 code units like Monaco
+    pub length: usize,
+}
+
+#[derive(Serialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<usize>, // 1-based
+    pub new_line: Option<usize>,
+    pub text: String, // Without the line ending
+    /// Changed words, for a removed or added line that was modified rather
+    /// than replaced outright
+    pub changes: Vec<InlineChange>,
+}
+
+#[derive(Serialize)]
+pub struct DiffHunk {
+    pub old_start: usize, // 1-based
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize)]
+pub struct FileDiff {
+    pub hunks: Vec<DiffHunk>,
+    pub added: usize,
+    pub removed: usize,
+    pub identical: bool,
+}
+
+impl From<DiffAlgorithm> for Algorithm {
+    fn from(algorithm: DiffAlgorithm) -> Self {
+        match algorithm {
+            DiffAlgorithm::Myers => Algorithm::Myers,
+            DiffAlgorithm::Patience => Algorithm::Patience,
+        }
+    }
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+fn trim_line_ending(line: &str) -> &str {
+    line.strip_suffix('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).unwrap_or(line)
+}
+
+// Pushes `length` onto the last range if it starts right where that one ends
+fn push_change(changes: &mut Vec<InlineChange>, column: usize, length: usize) {
+    match changes.last_mut() {
+        Some(last) if last.column + last.length == column => last.length += length,
+        _ => changes.push(InlineChange { column, length }),
+    }
+}
+
+/// Word-level differences between two versions of a line, as ranges in each.
+pub fn diff_words(
+    old: &str,
+    new: &str,
+    algorithm: DiffAlgorithm,
+) -> (Vec<InlineChange>, Vec<InlineChange>) {
+    let diff = TextDiff::configure()
+        .algorithm(algorithm.into())
+        .deadline(Instant::now() + DIFF_TIMEOUT)
+        .diff_words(old, new);
+
+    let mut old_changes = Vec::new();
+    let mut new_changes = Vec::new();
+    let mut old_column = 1;
+    let mut new_column = 1;
+
+    for change in diff.iter_all_changes() {
+        let length = utf16_len(change.value());
+        match change.tag() {
+            ChangeTag::Equal => {
+                old_column += length;
+                new_column += length;
+            }
+            ChangeTag::Delete => {
+                push_change(&mut old_changes, old_column, length);
+                old_column += length;
+            }
+            ChangeTag::Insert => {
+                push_change(&mut new_changes, new_column, length);
+                new_column += length;
+            }
+        }
+    }
+
+    (old_changes, new_changes)
+}
+
+fn line(kind: DiffLineKind, old_line: Option<usize>, new_line: Option<usize>, text: &str) -> DiffLine {
+    DiffLine {
+        kind,
+        old_line,
+        new_line,
+        text: trim_line_ending(text).to_string(),
+        changes: Vec::new(),
+    }
+}
+
+/// Compares two texts line by line. Replaced lines are paired up in order
+/// and get word-level changes as well.
+pub fn diff_texts(old: &str, new: &str, options: &DiffOptions) -> FileDiff {
+    let diff = TextDiff::configure()
+        .algorithm(options.algorithm.into())
+        .deadline(Instant::now() + DIFF_TIMEOUT)
+        .diff_lines(old, new);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+
+    let groups = match options.context_lines {
+        Some(context) => diff.grouped_ops(context),
+        None => vec![diff.ops().to_vec()],
+    };
+
+    let mut hunks = Vec::new();
+    let mut added = 0;
+    let mut removed = 0;
+
+    for ops in groups.iter().filter(|ops| ops.iter().any(|op| op.tag() != DiffTag::Equal)) {
+        let mut lines = Vec::new();
+
+        for op in ops {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => {
+                    for (i, j) in old_range.zip(new_range) {
+                        lines.push(line(DiffLineKind::Context, Some(i + 1), Some(j + 1), old_lines[i]));
+                    }
+                }
+                DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                    let first_removed = lines.len();
+                    for i in old_range.clone() {
+                        lines.push(line(DiffLineKind::Removed, Some(i + 1), None, old_lines[i]));
+                    }
+                    let first_added = lines.len();
+                    for j in new_range.clone() {
+                        lines.push(line(DiffLineKind::Added, None, Some(j + 1), new_lines[j]));
+                    }
+                    removed += old_range.len();
+                    added += new_range.len();
+
+                    if tag == DiffTag::Replace {
+                        for k in 0..old_range.len().min(new_range.len()) {
+                            let (old_changes, new_changes) = diff_words(
+                                &lines[first_removed + k].text,
+                                &lines[first_added + k].text,
+                                options.algorithm,
+                            );
+                            lines[first_removed + k].changes = old_changes;
+                            lines[first_added + k].changes = new_changes;
+                        }
+                    }
+                }
+            }
+        }
+
+        let (old_range, new_range) = hunk_ranges(ops);
+        hunks.push(DiffHunk {
+            old_start: old_range.start + 1,
+            old_lines: old_range.len(),
+            new_start: new_range.start + 1,
+            new_lines: new_range.len(),
+            lines,
+        });
+    }
+
+    FileDiff {
+        identical: hunks.is_empty(),
+        hunks,
+        added,
+        removed,
+    }
+}
+
+fn hunk_ranges(ops: &[DiffOp]) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+    let first = ops.first().map(|op| (op.old_range().start, op.new_range().start)).unwrap_or((0, 0));
+    let last = ops.last().map(|op| (op.old_range().end, op.new_range().end)).unwrap_or((0, 0));
+    (first.0..last.0, first.1..last.1)
+}
+
+fn side_text(side: DiffSide) -> Result<String, CommandError> {
+    match side {
+        DiffSide::Path(path) => Ok(read_text_file(path)?.content),
+        DiffSide::Text(text) => Ok(text),
+    }
+}
+
+/// Compares two files, or a file and in-memory text, and returns the
+/// differences as hunks. Used for "compare with saved", comparing files from
+/// the explorer and resolving conflicts.
+#[tauri::command(async)]
+pub fn compare_texts(
+    left: DiffSide,
+    right: DiffSide,
+    options: Option<DiffOptions>,
+) -> Result<FileDiff, CommandError> {
+    let options = options.unwrap_or_default();
+    let old = side_text(left)?;
+    let new = side_text(right)?;
+
+    Ok(diff_texts(&old, &new, &options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lines 1 to 20, with lines 2 and 19 changed on the new side
+    fn numbered_lines() -> (String, String) {
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new: String = (1..=20)
+            .map(|n| if n == 2 || n == 19 { format!("{} changed\n", n) } else { format!("{}\n", n) })
+            .collect();
+        (old, new)
+    }
+
+    fn kinds(diff: &FileDiff) -> Vec<DiffLineKind> {
+        diff.hunks.iter().flat_map(|h| h.lines.iter().map(|l| l.kind)).collect()
+    }
+
+    #[test]
+    fn identical_texts_have_no_hunks() {
+        let diff = diff_texts("a\nb\n", "a\nb\n", &DiffOptions::default());
+        assert!(diff.identical);
+        assert!(diff.hunks.is_empty());
+        assert_eq!((diff.added, diff.removed), (0, 0));
+    }
+
+    #[test]
+    fn replaced_line_gets_word_changes() {
+        let diff = diff_texts("let x = 1;\nend\n", "let y = 1;\nend\n", &DiffOptions::default());
+        assert_eq!((diff.added, diff.removed), (1, 1));
+
+        let hunk = &diff.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines), (1, 2, 1, 2));
+        assert_eq!(
+            kinds(&diff),
+            vec![DiffLineKind::Removed, DiffLineKind::Added, DiffLineKind::Context]
+        );
+
+        let removed = &hunk.lines[0];
+        assert_eq!(removed.text, "let x = 1;");
+        assert_eq!(removed.old_line, Some(1));
+        assert_eq!((removed.changes[0].column, removed.changes[0].length), (5, 1));
+    }
+
+    #[test]
+    fn distant_changes_split_into_hunks() {
+        let (old, new) = numbered_lines();
+        let diff = diff_texts(&old, &new, &DiffOptions::default());
+        assert_eq!(diff.hunks.len(), 2);
+        assert_eq!(diff.hunks[1].old_start, 16);
+    }
+
+    #[test]
+    fn no_context_returns_one_hunk() {
+        let (old, new) = numbered_lines();
+        let options = DiffOptions {
+            context_lines: None,
+            ..DiffOptions::default()
+        };
+        let diff = diff_texts(&old, &new, &options);
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].old_lines, 20);
+    }
+
+    #[test]
+    fn line_endings_are_trimmed_and_columns_count_utf16() {
+        let diff = diff_texts("é 😀 a\r\n", "é 😀 b\r\n", &DiffOptions::default());
+        let added = &diff.hunks[0].lines[1];
+        assert_eq!(added.text, "é 😀 b");
+        // "é " is 2 units and the emoji 2 more, so "b" starts at column 6
+        assert_eq!((added.changes[0].column, added.changes[0].length), (6, 1));
+    }
+}
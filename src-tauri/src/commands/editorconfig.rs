@@ -0,0 +1,363 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{encoding, workspace};
+use crate::error::CommandError;
+
+// Numeric ranges bigger than this are matched literally rather than blowing up the regex
+const MAX_RANGE_EXPANSION: i64 = 1000;
+
+/// The effective `.editorconfig` settings for one file. Unset (or `unset`)
+/// properties are `None` so the editor falls back to its own defaults.
+#[derive(Serialize, Default)]
+pub struct EditorConfig {
+    pub indent_style: Option<String>, // "tab" or "space"
+    pub indent_size: Option<u32>,
+    pub tab_width: Option<u32>,
+    pub end_of_line: Option<String>, // "lf", "crlf" or "cr"
+    pub charset: Option<String>,     // "utf-8-bom" or an encoding label, e.g. "utf-8", "latin1", "utf-16le"
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+    pub max_line_length: Option<u32>,
+}
+
+struct Section {
+    glob: String,
+    properties: Vec<(String, String)>,
+}
+
+struct ConfigFile {
+    root: bool,
+    sections: Vec<Section>,
+}
+
+fn parse(content: &str) -> ConfigFile {
+    let mut file = ConfigFile {
+        root: false,
+        sections: Vec::new(),
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if let Some(end) = line.rfind(']') {
+                file.sections.push(Section {
+                    glob: line[1..end].to_string(),
+                    properties: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_string();
+
+        match file.sections.last_mut() {
+            Some(section) => section.properties.push((key, value)),
+            // Only `root` means anything before the first section
+            None if key == "root" => file.root = value.eq_ignore_ascii_case("true"),
+            None => {}
+        }
+    }
+
+    file
+}
+
+// Index of the '}' closing the '{' at `open`, if any
+fn closing_brace(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Splits brace contents on the commas that aren't inside nested braces
+fn split_alternatives(chars: &[char]) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if i + 1 < chars.len() => {
+                parts.last_mut().unwrap().push(c);
+                i += 1;
+                parts.last_mut().unwrap().push(chars[i]);
+                i += 1;
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(String::new());
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(c);
+        i += 1;
+    }
+    parts
+}
+
+// `{n1..n2}` as an alternation of every number in the range
+fn numeric_range(inner: &str) -> Option<String> {
+    let (from, to) = inner.split_once("..")?;
+    let (from, to) = (from.parse::<i64>().ok()?, to.parse::<i64>().ok()?);
+    let (low, high) = (from.min(to), from.max(to));
+    if high - low > MAX_RANGE_EXPANSION {
+        return None;
+    }
+    let numbers: Vec<String> = (low..=high).map(|n| n.to_string()).collect();
+    Some(format!("(?:{})", numbers.join("|")))
+}
+
+// Translates an EditorConfig glob into a regex. Unlike gitignore-style globs,
+// `**` may appear anywhere and matches across directories.
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                regex.push_str(".*");
+                i += 1;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                regex.push_str(&regex::escape(&chars[i].to_string()));
+            }
+            '[' => match chars[i + 1..].iter().position(|&c| c == ']' || c == '/') {
+                Some(offset) if chars[i + 1 + offset] == ']' => {
+                    let class: String = chars[i + 1..i + 1 + offset].iter().collect();
+                    let (negated, class) = match class.strip_prefix('!') {
+                        Some(rest) => (true, rest.to_string()),
+                        None => (false, class),
+                    };
+                    let class = class.replace('\\', "\\\\").replace('[', "\\[");
+                    regex.push_str(&format!("[{}{}]", if negated { "^" } else { "" }, class));
+                    i += offset + 1;
+                }
+                _ => regex.push_str("\\["),
+            },
+            '{' => match closing_brace(&chars, i) {
+                Some(close) => {
+                    let inner = &chars[i + 1..close];
+                    let inner_str: String = inner.iter().collect();
+                    let alternatives = split_alternatives(inner);
+
+                    if let Some(range) = numeric_range(&inner_str) {
+                        regex.push_str(&range);
+                    } else if alternatives.len() > 1 {
+                        let alternatives: Vec<String> = alternatives.iter().map(|a| glob_to_regex(a)).collect();
+                        regex.push_str(&format!("(?:{})", alternatives.join("|")));
+                    } else {
+                        regex.push_str(&format!("\\{{{}\\}}", glob_to_regex(&inner_str)));
+                    }
+                    i = close;
+                }
+                None => regex.push_str("\\{"),
+            },
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    regex
+}
+
+// Section globs without a '/' match the file name at any depth; the rest are
+// relative to the directory holding the .editorconfig
+fn section_matcher(glob: &str) -> Option<Regex> {
+    let pattern = if let Some(anchored) = glob.strip_prefix('/') {
+        glob_to_regex(anchored)
+    } else if glob.contains('/') {
+        glob_to_regex(glob)
+    } else {
+        format!("(?:.*/)?{}", glob_to_regex(glob))
+    };
+
+    Regex::new(&format!("^{}$", pattern)).ok()
+}
+
+fn parse_number(value: &str) -> Option<u32> {
+    value.parse().ok()
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn resolve(properties: &HashMap<String, String>) -> EditorConfig {
+    let get = |key: &str| properties.get(key).map(|v| v.as_str()).filter(|v| *v != "unset");
+
+    let mut config = EditorConfig {
+        indent_style: get("indent_style").filter(|v| *v == "tab" || *v == "space").map(String::from),
+        indent_size: get("indent_size").and_then(parse_number),
+        tab_width: get("tab_width").and_then(parse_number),
+        end_of_line: get("end_of_line")
+            .filter(|v| matches!(*v, "lf" | "crlf" | "cr"))
+            .map(String::from),
+        // Only charsets the editor can actually save in
+        charset: get("charset")
+            .filter(|v| *v == "utf-8-bom" || encoding::is_known(v))
+            .map(String::from),
+        trim_trailing_whitespace: get("trim_trailing_whitespace").and_then(parse_bool),
+        insert_final_newline: get("insert_final_newline").and_then(parse_bool),
+        max_line_length: get("max_line_length").and_then(parse_number),
+    };
+
+    // indent_size = tab (or indent_style = tab alone) means "use tab_width",
+    // and tab_width defaults to the indent size
+    let indent_is_tab = get("indent_size") == Some("tab")
+        || (config.indent_style.as_deref() == Some("tab") && get("indent_size").is_none());
+    if indent_is_tab {
+        config.indent_size = config.tab_width;
+    }
+    if config.tab_width.is_none() {
+        config.tab_width = config.indent_size;
+    }
+
+    config
+}
+
+/// Resolves the `.editorconfig` settings that apply to `path`, reading every
+/// `.editorconfig` from the file's directory up to `workspace_root` (or the
+/// open workspace root holding the file), stopping early at one marked
+/// `root = true`. Closer files and later sections take precedence.
+#[tauri::command]
+pub fn get_editorconfig(path: String, workspace_root: Option<String>) -> Result<EditorConfig, CommandError> {
+    let file_path = workspace::check_path(&path)?;
+    let file_dir = file_path
+        .parent()
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid file path: {}", path)))?;
+
+    // Compared against resolved directories, so resolved too. Nothing outside
+    // the workspace is read, whatever `workspace_root` says.
+    let stop_root = workspace_root.as_deref().map(workspace::check_path).transpose()?;
+    let sandbox_root = workspace::containing_root(&file_path);
+
+    let mut configs: Vec<(&Path, ConfigFile)> = Vec::new();
+    for dir in file_dir.ancestors() {
+        if let Ok(content) = fs::read_to_string(dir.join(".editorconfig")) {
+            let config = parse(&content);
+            let is_root = config.root;
+            configs.push((dir, config));
+            if is_root {
+                break;
+            }
+        }
+
+        if Some(dir) == stop_root.as_deref() || Some(dir) == sandbox_root.as_deref() {
+            break;
+        }
+    }
+
+    // Apply the outermost file first so closer ones override it
+    let mut properties: HashMap<String, String> = HashMap::new();
+    for (dir, config) in configs.iter().rev() {
+        let Ok(relative) = file_path.strip_prefix(dir) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+
+        for section in &config.sections {
+            let matches = section_matcher(&section.glob)
+                .map(|m| m.is_match(&relative))
+                .unwrap_or(false);
+            if !matches {
+                continue;
+            }
+
+            for (key, value) in &section.properties {
+                properties.insert(key.clone(), value.to_lowercase());
+            }
+        }
+    }
+
+    Ok(resolve(&properties))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(glob: &str, path: &str) -> bool {
+        Regex::new(&format!("^{}$", glob_to_regex(glob))).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn glob_braces_match_alternatives() {
+        assert!(matches("*.{js,ts}", "app.js"));
+        assert!(matches("*.{js,ts}", "app.ts"));
+        assert!(!matches("*.{js,ts}", "app.rs"));
+        assert!(matches("{a,{b,c}}.txt", "c.txt"));
+    }
+
+    #[test]
+    fn glob_single_brace_item_is_literal() {
+        assert!(matches("{a}.txt", "{a}.txt"));
+        assert!(!matches("{a}.txt", "a.txt"));
+    }
+
+    #[test]
+    fn glob_double_star_crosses_directories() {
+        assert!(matches("src/**/*.rs", "src/a/b/lib.rs"));
+        assert!(matches("**.md", "docs/guide/intro.md"));
+        assert!(!matches("*.md", "docs/intro.md"));
+    }
+
+    #[test]
+    fn glob_negated_class() {
+        assert!(matches("file[!0-9].txt", "filea.txt"));
+        assert!(!matches("file[!0-9].txt", "file1.txt"));
+        assert!(matches("file[ab].txt", "fileb.txt"));
+    }
+
+    #[test]
+    fn glob_numeric_range() {
+        assert!(matches("v{1..3}.txt", "v1.txt"));
+        assert!(matches("v{1..3}.txt", "v3.txt"));
+        assert!(!matches("v{1..3}.txt", "v4.txt"));
+        assert!(matches("v{3..1}.txt", "v2.txt"));
+    }
+
+    #[test]
+    fn glob_escapes_regex_characters() {
+        assert!(matches("a+b.txt", "a+b.txt"));
+        assert!(!matches("a+b.txt", "aab.txt"));
+        assert!(matches("\\*.txt", "*.txt"));
+    }
+}
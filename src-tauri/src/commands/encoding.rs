@@ -0,0 +1,238 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+use crate::error::{CommandError, ErrorKind};
+
+// How much of a file is looked at when sniffing for binary content and UTF-16
+const SNIFF_LEN: usize = 8192;
+
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: String, // WHATWG encoding name, e.g. "UTF-8", "windows-1252"
+    pub has_bom: bool,
+}
+
+// UTF-16 without a BOM: lots of NUL bytes, nearly all of them on one side,
+// and the sample has to decode to text as well
+fn sniff_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    if sample.len() < 4 {
+        return None;
+    }
+
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+
+    let encoding = if odd_zeros * 10 > pairs * 4 && even_zeros * 20 < pairs {
+        UTF_16LE
+    } else if even_zeros * 10 > pairs * 4 && odd_zeros * 20 < pairs {
+        UTF_16BE
+    } else {
+        return None;
+    };
+
+    decodes_to_text(&sample[..pairs * 2], encoding).then_some(encoding)
+}
+
+// Arrays of small 16-bit integers have the same zeros as UTF-16 but decode to
+// control characters. Text has no unpaired surrogates (except one cut off at
+// the end of the sample) and hardly any control characters besides whitespace.
+fn decodes_to_text(sample: &[u8], encoding: &'static Encoding) -> bool {
+    let (text, _) = encoding.decode_without_bom_handling(sample);
+    let text = text.strip_suffix('\u{fffd}').unwrap_or(&text);
+
+    let mut chars = 0;
+    let mut controls = 0;
+    for c in text.chars() {
+        if c == '\u{fffd}' {
+            return false;
+        }
+        chars += 1;
+        if c.is_control() && !c.is_whitespace() {
+            controls += 1;
+        }
+    }
+
+    controls * 50 < chars
+}
+
+// Valid UTF-8; a prefix may end with a multi-byte character cut in half
+fn is_utf8(bytes: &[u8], last: bool) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => !last && e.error_len().is_none(),
+    }
+}
+
+/// Works out the encoding of `bytes` and the length of its byte order mark.
+/// `last` is false when `bytes` is only the start of a file. Returns `None`
+/// for binary data.
+pub fn detect_encoding(bytes: &[u8], last: bool) -> Option<(&'static Encoding, usize)> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return Some((encoding, bom_len));
+    }
+
+    let sample = &bytes[..std::cmp::min(bytes.len(), SNIFF_LEN)];
+    if let Some(encoding) = sniff_utf16(sample) {
+        return Some((encoding, 0));
+    }
+
+    if sample.contains(&0) {
+        return None;
+    }
+
+    if is_utf8(bytes, last) {
+        return Some((UTF_8, 0));
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, last);
+    Some((detector.guess(None, false), 0))
+}
+
+/// Decodes file content, detecting the encoding from a BOM or the content
+/// itself. Fails for binary data and for bytes that aren't valid in the
+/// detected encoding, since saving such text back would corrupt the file.
+pub fn decode(bytes: &[u8]) -> Result<DecodedText, CommandError> {
+    let (encoding, bom_len) = detect_encoding(bytes, true)
+        .ok_or_else(|| CommandError::new(ErrorKind::Binary, "Binary file cannot be displayed"))?;
+
+    let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    if had_errors {
+        return Err(CommandError::new(
+            ErrorKind::NotUtf8,
+            format!("File is not valid {} text", encoding.name()),
+        ));
+    }
+
+    Ok(DecodedText {
+        text: text.into_owned(),
+        encoding: encoding.name().to_string(),
+        has_bom: bom_len > 0,
+    })
+}
+
+/// Whether `label` names an encoding `encode` can save in.
+pub fn is_known(label: &str) -> bool {
+    Encoding::for_label(label.as_bytes()).is_some()
+}
+
+/// Encodes `text` for saving in the encoding named by `label` (any WHATWG
+/// label, e.g. "utf-8", "latin1", "shift_jis"), optionally with a BOM.
+/// Fails if the text contains characters the encoding can't represent.
+pub fn encode(text: &str, label: &str, with_bom: bool) -> Result<Vec<u8>, CommandError> {
+    let encoding = Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| CommandError::invalid_input(format!("Unknown encoding: {}", label)))?;
+
+    // encoding_rs only decodes UTF-16, so it is written out by hand
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let little_endian = encoding == UTF_16LE;
+        let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+        let units = (with_bom.then_some('\u{feff}').into_iter())
+            .map(|c| c as u16)
+            .chain(text.encode_utf16());
+        for unit in units {
+            bytes.extend_from_slice(&if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() });
+        }
+        return Ok(bytes);
+    }
+
+    let (encoded, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        return Err(CommandError::new(
+            ErrorKind::Unencodable,
+            format!("Text contains characters that cannot be saved as {}", encoding.name()),
+        ));
+    }
+
+    let mut bytes = Vec::with_capacity(encoded.len() + 3);
+    // Only UTF-8 has a BOM among the encodings encoding_rs can write
+    if with_bom && encoding == UTF_8 {
+        bytes.extend_from_slice(b"\xEF\xBB\xBF");
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_bom_round_trips() {
+        let bytes = encode("héllo", "utf-8", true).unwrap();
+        assert_eq!(&bytes[..3], b"\xEF\xBB\xBF");
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.text, "héllo");
+        assert_eq!(decoded.encoding, "UTF-8");
+        assert!(decoded.has_bom);
+    }
+
+    #[test]
+    fn utf16_bom_round_trips() {
+        for label in ["utf-16le", "utf-16be"] {
+            let bytes = encode("a€b", label, true).unwrap();
+            let decoded = decode(&bytes).unwrap();
+            assert_eq!(decoded.text, "a€b");
+            assert!(decoded.encoding.eq_ignore_ascii_case(label));
+            assert!(decoded.has_bom);
+        }
+    }
+
+    #[test]
+    fn utf16_without_bom_is_sniffed() {
+        let little = encode("plain ascii text", "utf-16le", false).unwrap();
+        assert_eq!(sniff_utf16(&little), Some(UTF_16LE));
+
+        let big = encode("plain ascii text", "utf-16be", false).unwrap();
+        assert_eq!(sniff_utf16(&big), Some(UTF_16BE));
+        assert_eq!(decode(&big).unwrap().text, "plain ascii text");
+    }
+
+    #[test]
+    fn sniffing_needs_mostly_one_sided_zeros() {
+        assert_eq!(sniff_utf16(b"ab"), None);
+        assert_eq!(sniff_utf16(b"plain ascii text"), None);
+        assert_eq!(sniff_utf16(&[0; 16]), None);
+    }
+
+    #[test]
+    fn small_integer_arrays_are_binary() {
+        let samples: Vec<i16> = (0..512).map(|i| (i % 200) - 100).collect();
+        let little: Vec<u8> = samples.iter().flat_map(|n| n.to_le_bytes()).collect();
+        let big: Vec<u8> = samples.iter().flat_map(|n| n.to_be_bytes()).collect();
+        let counts: Vec<u8> = (0..512u16).flat_map(|n| (n % 64).to_le_bytes()).collect();
+
+        for bytes in [little, big, counts] {
+            assert_eq!(decode(&bytes).err().unwrap().kind, ErrorKind::Binary);
+        }
+    }
+
+    #[test]
+    fn legacy_encoding_round_trips() {
+        let bytes = encode("café", "latin1", false).unwrap();
+        assert_eq!(bytes, b"caf\xe9");
+        assert_eq!(decode(&bytes).unwrap().text, "café");
+    }
+
+    #[test]
+    fn binary_is_rejected() {
+        let error = decode(b"\x89PNG\x00\x00\x00\x0dIHDR").err().unwrap();
+        assert_eq!(error.kind, ErrorKind::Binary);
+    }
+
+    #[test]
+    fn unencodable_text_is_rejected() {
+        let error = encode("日本", "latin1", false).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Unencodable);
+        assert_eq!(encode("x", "no-such-encoding", false).unwrap_err().kind, ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn known_labels() {
+        assert!(is_known("latin1"));
+        assert!(is_known("utf-16le"));
+        assert!(!is_known("utf-8-bom"));
+    }
+}
@@ -0,0 +1,327 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ignore::{WalkBuilder, WalkState};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use tauri::{AppHandle, Emitter};
+
+use super::watcher::FsChange;
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+// Global file index storage, keyed by canonical workspace root path
+lazy_static::lazy_static! {
+    static ref FILE_INDEXES: Arc<Mutex<HashMap<String, Arc<FileIndex>>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+const DEFAULT_QUERY_LIMIT: usize = 50;
+
+// Extra weight for matches that land in the file name rather than the directories
+const FILE_NAME_BONUS: i64 = 20;
+
+struct FileIndex {
+    root: PathBuf,
+    // Paths relative to the root, using '/' separators on every platform
+    files: RwLock<HashSet<String>>,
+    ready: AtomicBool,
+    // Watcher changes that arrive during the initial walk, applied after it
+    // so the walk can't bring back a file deleted while it was running
+    pending: Mutex<Vec<FsChange>>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileIndexReady {
+    pub root: String,
+    pub file_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct FileMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub score: i64,
+    pub positions: Vec<usize>, // Matched character indices into relative_path
+}
+
+#[derive(Serialize)]
+pub struct FileQueryResult {
+    pub matches: Vec<FileMatch>,
+    pub indexing: bool, // True while the initial walk is still running
+}
+
+fn walker(path: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(path);
+    builder
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    builder
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    if relative.is_empty() {
+        None
+    } else {
+        Some(relative)
+    }
+}
+
+// Walks `path` in parallel and returns every non-ignored file below it
+fn collect_files(root: &Path, path: &Path) -> Vec<String> {
+    let collected = Mutex::new(Vec::new());
+
+    walker(path).build_parallel().run(|| {
+        Box::new(|entry| {
+            if let Ok(entry) = entry {
+                if entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+                    if let Some(relative) = relative_path(root, entry.path()) {
+                        collected.lock().unwrap().push(relative);
+                    }
+                }
+            }
+            WalkState::Continue
+        })
+    });
+
+    collected.into_inner().unwrap()
+}
+
+impl FileIndex {
+    // Whether `path` would be reached by the walker: every directory between
+    // the root and `path` has to show up when listing its parent through the
+    // same ignore rules. Listings are cached for the duration of one batch.
+    fn is_visible(&self, path: &Path, listings: &mut HashMap<PathBuf, HashSet<PathBuf>>) -> bool {
+        if path == self.root {
+            return true;
+        }
+
+        let Some(parent) = path.parent().filter(|p| p.starts_with(&self.root)) else {
+            return false;
+        };
+
+        if !self.is_visible(parent, listings) {
+            return false;
+        }
+
+        listings
+            .entry(parent.to_path_buf())
+            .or_insert_with(|| {
+                walker(parent)
+                    .max_depth(Some(1))
+                    .build()
+                    .flatten()
+                    .map(|entry| entry.into_path())
+                    .collect()
+            })
+            .contains(path)
+    }
+
+    // Brings the index up to date with a batch of watcher events. New files
+    // are re-checked against the ignore rules and new directories are
+    // indexed completely.
+    fn apply_changes(&self, changes: &[FsChange]) {
+        let mut removed: Vec<String> = Vec::new();
+        let mut created: Vec<PathBuf> = Vec::new();
+
+        for change in changes {
+            if let Some(old_path) = &change.old_path {
+                removed.extend(relative_path(&self.root, Path::new(old_path)));
+            }
+
+            match change.kind.as_str() {
+                "delete" => removed.extend(relative_path(&self.root, Path::new(&change.path))),
+                "create" | "rename" => created.push(PathBuf::from(&change.path)),
+                _ => {}
+            }
+        }
+
+        // Walk outside the lock so queries aren't blocked on disk access
+        let mut listings = HashMap::new();
+        let mut added: Vec<String> = Vec::new();
+        for path in created {
+            if !self.is_visible(&path, &mut listings) {
+                continue;
+            }
+
+            if path.is_dir() {
+                added.extend(collect_files(&self.root, &path));
+            } else {
+                added.extend(relative_path(&self.root, &path));
+            }
+        }
+
+        let mut files = self.files.write().unwrap();
+
+        for relative in removed {
+            // A removed directory takes everything below it along
+            let prefix = format!("{}/", relative);
+            files.retain(|f| f != &relative && !f.starts_with(&prefix));
+        }
+
+        files.extend(added);
+    }
+
+    fn query(&self, query: &str, limit: usize) -> Vec<FileMatch> {
+        let files = self.files.read().unwrap();
+        let to_match = |relative: &String, score: i64, positions: Vec<usize>| FileMatch {
+            path: self.root.join(relative).to_string_lossy().to_string(),
+            relative_path: relative.clone(),
+            score,
+            positions,
+        };
+
+        if query.trim().is_empty() {
+            // No query: shallow, short paths first
+            let mut all: Vec<&String> = files.iter().collect();
+            all.sort_by(|a, b| a.matches('/').count().cmp(&b.matches('/').count()).then_with(|| a.cmp(b)));
+            return all.into_iter().take(limit).map(|f| to_match(f, 0, Vec::new())).collect();
+        }
+
+        let matcher = SkimMatcherV2::default().smart_case();
+        let query: String = query.chars().filter(|c| !c.is_whitespace()).collect();
+
+        let mut matches: Vec<FileMatch> = files
+            .iter()
+            .filter_map(|relative| {
+                let (mut score, mut positions) = matcher.fuzzy_indices(relative, &query)?;
+
+                // Prefer a match that fits entirely inside the file name
+                let name_start = relative.rfind('/').map(|i| i + 1).unwrap_or(0);
+                if let Some((name_score, name_positions)) = matcher.fuzzy_indices(&relative[name_start..], &query) {
+                    if name_score + FILE_NAME_BONUS > score {
+                        let offset = relative[..name_start].chars().count();
+                        score = name_score + FILE_NAME_BONUS;
+                        positions = name_positions.into_iter().map(|p| p + offset).collect();
+                    }
+                }
+
+                Some(to_match(relative, score, positions))
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.relative_path.len().cmp(&b.relative_path.len()))
+                .then_with(|| a.relative_path.cmp(&b.relative_path))
+        });
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// Called by the file watcher so indexes stay current without re-walking.
+pub fn apply_changes(root: &Path, changes: &[FsChange]) {
+    let index = FILE_INDEXES.lock().unwrap().get(&*root.to_string_lossy()).cloned();
+
+    if let Some(index) = index {
+        let mut pending = index.pending.lock().unwrap();
+        if !index.ready.load(Ordering::Relaxed) {
+            pending.extend_from_slice(changes);
+            return;
+        }
+        drop(pending);
+
+        index.apply_changes(changes);
+    }
+}
+
+fn index_not_found(root_path: &str) -> CommandError {
+    CommandError::new(ErrorKind::NotFound, format!("File index not found: {}", root_path))
+}
+
+/// Starts indexing `root_path` in the background, honoring .gitignore and
+/// .ignore files. Emits `file-index-ready` once the initial walk completes.
+/// While a file watcher runs on the same root the index is kept up to date.
+#[tauri::command]
+pub fn build_file_index(app: AppHandle, root_path: String) -> Result<(), CommandError> {
+    use std::collections::hash_map::Entry;
+
+    let root = workspace::check_path(&root_path)?;
+
+    if !root.is_dir() {
+        let message = format!("Path is not a directory: {}", root_path);
+        return Err(CommandError::invalid_input(message).with_path(&root_path));
+    }
+
+    let index = {
+        let mut indexes = FILE_INDEXES.lock().unwrap();
+        match indexes.entry(root.to_string_lossy().to_string()) {
+            // Already indexed or indexing
+            Entry::Occupied(_) => return Ok(()),
+            Entry::Vacant(entry) => entry
+                .insert(Arc::new(FileIndex {
+                    root: root.clone(),
+                    files: RwLock::new(HashSet::new()),
+                    ready: AtomicBool::new(false),
+                    pending: Mutex::new(Vec::new()),
+                }))
+                .clone(),
+        }
+    };
+
+    thread::spawn(move || {
+        let files = collect_files(&root, &root);
+        index.files.write().unwrap().extend(files);
+
+        // Still holding the lock when marking the index ready, so no batch
+        // can be applied before the ones queued during the walk
+        let mut pending = index.pending.lock().unwrap();
+        index.apply_changes(&pending);
+        pending.clear();
+        index.ready.store(true, Ordering::Relaxed);
+        drop(pending);
+
+        let file_count = index.files.read().unwrap().len();
+
+        let _ = app.emit("file-index-ready", FileIndexReady {
+            root: root_path,
+            file_count,
+        });
+    });
+
+    Ok(())
+}
+
+/// Fuzzy-matches `query` against the indexed paths and returns the best
+/// `limit` matches with the matched character positions for highlighting.
+#[tauri::command]
+pub fn query_file_index(
+    root_path: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<FileQueryResult, CommandError> {
+    let index = FILE_INDEXES
+        .lock()
+        .unwrap()
+        .get(&workspace::root_key(&root_path))
+        .cloned()
+        .ok_or_else(|| index_not_found(&root_path))?;
+
+    Ok(FileQueryResult {
+        matches: index.query(&query, limit.unwrap_or(DEFAULT_QUERY_LIMIT)),
+        indexing: !index.ready.load(Ordering::Relaxed),
+    })
+}
+
+#[tauri::command]
+pub fn drop_file_index(root_path: String) -> Result<(), CommandError> {
+    let mut indexes = FILE_INDEXES.lock().unwrap();
+
+    if indexes.remove(&workspace::root_key(&root_path)).is_some() {
+        Ok(())
+    } else {
+        Err(index_not_found(&root_path))
+    }
+}
+
+/// Drops the index for `root`, if any, e.g. when its workspace is closed.
+pub fn drop_root(root: &Path) {
+    FILE_INDEXES.lock().unwrap().remove(&*root.to_string_lossy());
+}
@@ -1,6 +1,22 @@
-use serde::Serialize;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use super::encoding;
+use super::history::{self, SnapshotSource};
+use super::journal::{self, FileOperation};
+use super::preview;
+use super::text_format::{self, detect_line_ending, LineEnding, SaveFormat};
+use super::trash::{move_to_trash, TrashHandle};
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
 
 #[derive(Serialize)]
 pub struct FileEntry {
@@ -9,214 +25,1173 @@ pub struct FileEntry {
     pub is_directory: bool,
     pub is_symlink: bool,
     pub size: u64,
+    pub is_ignored: bool,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ReadDirectoryOptions {
+    #[serde(default)]
+    pub hide_ignored: bool,
+    #[serde(default)]
+    pub hide_hidden: bool,
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
+// How much of a file get_file_info looks at to guess its type
+const FILE_INFO_PREFIX_LEN: u64 = 64 * 1024;
+
 #[derive(Serialize)]
 pub struct FileInfo {
     pub size: u64,
     pub is_binary: bool,
     pub is_readonly: bool,
+    pub mtime_ms: Option<u64>,
+    pub ctime_ms: Option<u64>, // Status change time on Unix, creation time elsewhere
+    pub mode: Option<u32>,     // Unix permission bits
+    pub owner: Option<String>,
+    pub symlink_target: Option<String>,
+    pub mime: Option<String>,
+    pub encoding: Option<String>,
+    pub line_ending: Option<LineEnding>,
 }
 
-#[tauri::command]
-pub fn read_directory(path: String) -> Result<Vec<FileEntry>, String> {
-    let dir_path = Path::new(&path);
+/// Identifies a specific on-disk version of a file, so a save can detect
+/// that something else changed the file since it was read.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct FileFingerprint {
+    pub mtime_ms: u64,
+    pub size: u64,
+    pub hash: String,
+}
+
+impl FileFingerprint {
+    /// Whether both describe the same content. A file that was touched but
+    /// not changed (same size and hash, newer mtime) counts as the same.
+    pub fn same_content(&self, other: &FileFingerprint) -> bool {
+        self.size == other.size && self.hash == other.hash
+    }
+}
+
+#[derive(Serialize)]
+pub struct TextFile {
+    pub content: String,
+    pub fingerprint: FileFingerprint,
+    pub encoding: String, // Detected encoding, e.g. "UTF-8", "UTF-16LE", "windows-1252"
+    pub has_bom: bool,
+    pub line_ending: Option<LineEnding>, // None if the file has no line breaks
+    pub has_final_newline: bool,
+}
+
+// Save failures for lack of permission are reported as `ReadOnly`, so the UI
+// can offer to retry the save with elevated permissions
+fn write_error(path: &Path, action: &str, e: std::io::Error) -> CommandError {
+    let mut error = CommandError::io(action, e).with_path(path);
+    if error.kind == ErrorKind::PermissionDenied {
+        error.kind = ErrorKind::ReadOnly;
+    }
+    error
+}
+
+pub fn fingerprint(metadata: &fs::Metadata, bytes: &[u8]) -> FileFingerprint {
+    let mtime_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    FileFingerprint {
+        mtime_ms,
+        size: bytes.len() as u64,
+        hash: content_hash(bytes),
+    }
+}
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// The error for a save that would overwrite changes made since the file was
+/// read. It carries the file's current fingerprint, or none if it was deleted.
+pub fn modified_on_disk(path: impl AsRef<Path>, current: Option<FileFingerprint>) -> CommandError {
+    let message = format!("File was modified on disk: {}", path.as_ref().display());
+    CommandError {
+        current,
+        ..CommandError::new(ErrorKind::ModifiedOnDisk, message).with_path(path)
+    }
+}
+
+/// Replaces the contents of `path` without ever leaving a half-written file
+/// behind: the data goes to a temp file in the same directory, is synced to
+/// disk and then renamed over the target. Symlinks are followed so the real
+/// file is replaced rather than the link, and the original permissions and
+/// ownership are carried over to the new file.
+pub fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), CommandError> {
+    replace_file(path, bytes, None)
+}
+
+fn replace_file(path: &Path, bytes: &[u8], expected: Option<&FileFingerprint>) -> Result<(), CommandError> {
+    let target = if path.exists() {
+        fs::canonicalize(path).map_err(|e| write_error(path, "resolve path", e))?
+    } else {
+        path.to_path_buf()
+    };
+
+    let existing = fs::metadata(&target).ok();
+    if existing.as_ref().map(|m| m.permissions().readonly()).unwrap_or(false) {
+        let message = format!("File is read-only: {}", target.display());
+        return Err(CommandError::new(ErrorKind::ReadOnly, message).with_path(&target));
+    }
+
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        ".{}.",
+        target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    );
+
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(&prefix).suffix(".tmp");
+
+    // Temp files are created private by default; new files should get the
+    // usual umask-derived mode instead
+    #[cfg(unix)]
+    if existing.is_none() {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+
+    let mut temp = builder
+        .tempfile_in(dir)
+        .map_err(|e| write_error(&target, "create temporary file", e))?;
 
-    if !dir_path.exists() {
-        return Err(format!("Directory does not exist: {}", path));
+    temp.write_all(bytes)
+        .map_err(|e| write_error(&target, "write file", e))?;
+
+    if let Some(metadata) = &existing {
+        temp.as_file()
+            .set_permissions(metadata.permissions())
+            .map_err(|e| write_error(&target, "preserve permissions", e))?;
+
+        // Only root can give files away, so a failed chown is not fatal
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let _ = std::os::unix::fs::fchown(temp.as_file(), Some(metadata.uid()), Some(metadata.gid()));
+        }
+    }
+
+    temp.as_file()
+        .sync_all()
+        .map_err(|e| write_error(&target, "sync file", e))?;
+
+    // The temp file is removed when dropped, if the file changed meanwhile
+    check_unchanged(&target, expected)?;
+
+    temp.persist(&target)
+        .map_err(|e| write_error(&target, "replace file", e.error))?;
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Ok(dir_handle) = fs::File::open(dir) {
+        let _ = dir_handle.sync_all();
+    }
+
+    Ok(())
+}
+
+fn current_fingerprint(path: &Path) -> Result<Option<FileFingerprint>, CommandError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let metadata = fs::metadata(path).map_err(|e| CommandError::io("get file metadata", e).with_path(path))?;
+    let bytes = fs::read(path).map_err(|e| CommandError::io("read file", e).with_path(path))?;
+    Ok(Some(fingerprint(&metadata, &bytes)))
+}
+
+fn check_unchanged(path: &Path, expected: Option<&FileFingerprint>) -> Result<(), CommandError> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let current = current_fingerprint(path)?;
+    if current.as_ref().map(|c| c.same_content(expected)).unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(modified_on_disk(path, current))
     }
+}
+
+/// Saves `bytes` over a local file the way every editor save does: nothing is
+/// written if the file changed since `expected` was read, the content being
+/// replaced goes to the local history, and the file is replaced atomically.
+/// `path` must already be checked against the workspace.
+pub fn save_file(
+    path: &Path,
+    bytes: &[u8],
+    expected: Option<&FileFingerprint>,
+) -> Result<FileFingerprint, CommandError> {
+    check_unchanged(path, expected)?;
 
-    if !dir_path.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
+    // A failed snapshot shouldn't block the save
+    if let Err(e) = history::record(path, SnapshotSource::Save) {
+        log::warn!("Failed to snapshot {} before saving: {}", path.display(), e);
     }
 
+    // Checked again once the new content is on disk, right before it
+    // replaces the file, to narrow the window for a change to slip in
+    replace_file(path, bytes, expected)?;
+
+    let metadata = fs::metadata(path).map_err(|e| write_error(path, "get file metadata", e))?;
+    Ok(fingerprint(&metadata, bytes))
+}
+
+// Ignore files that apply to entries of `dir`, closest first, stopping at the
+// repository root. `.ignore` takes precedence over `.gitignore` in the same
+// directory, as it does for ripgrep.
+pub fn ignore_chain(dir: &Path) -> Vec<Gitignore> {
+    let mut chain = Vec::new();
+
+    for ancestor in dir.ancestors() {
+        for name in [".ignore", ".gitignore"] {
+            let file = ancestor.join(name);
+            if file.is_file() {
+                chain.push(Gitignore::new(&file).0);
+            }
+        }
+
+        let git_dir = ancestor.join(".git");
+        if git_dir.exists() {
+            // Patterns in info/exclude are relative to the repository root
+            let mut builder = GitignoreBuilder::new(ancestor);
+            if builder.add(git_dir.join("info/exclude")).is_none() {
+                if let Ok(matcher) = builder.build() {
+                    chain.push(matcher);
+                }
+            }
+            break;
+        }
+    }
+
+    chain
+}
+
+pub fn is_ignored(chain: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    if path.file_name().map(|n| n == ".git").unwrap_or(false) {
+        return true;
+    }
+
+    for matcher in chain {
+        match matcher.matched_path_or_any_parents(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+    }
+
+    false
+}
+
+fn build_exclude_set(patterns: &[String]) -> Result<GlobSet, CommandError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| CommandError::invalid_input(format!("Invalid exclude pattern '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| CommandError::invalid_input(format!("Failed to build exclude patterns: {}", e)))
+}
+
+// Directories first, then by name ignoring case. Exact name breaks ties, so
+// the order is total and can be resumed from any entry.
+pub fn compare_entries(a_dir: bool, a_name: &str, b_dir: bool, b_name: &str) -> std::cmp::Ordering {
+    b_dir
+        .cmp(&a_dir)
+        .then_with(|| a_name.to_lowercase().cmp(&b_name.to_lowercase()))
+        .then_with(|| a_name.cmp(b_name))
+}
+
+// Lists the entries of `dir_path` that pass `options`, sorted. The directory
+// is read at `resolved`, its checked location; entry paths are given below
+// `dir_path` as the caller named it.
+fn list_directory(
+    dir_path: &Path,
+    resolved: &Path,
+    options: &ReadDirectoryOptions,
+    exclude_set: &GlobSet,
+) -> Result<Vec<FileEntry>, CommandError> {
+    let ignore_chain = ignore_chain(resolved);
     let mut entries = Vec::new();
 
-    match fs::read_dir(dir_path) {
+    match fs::read_dir(resolved) {
         Ok(dir_entries) => {
-            for entry in dir_entries {
-                if let Ok(entry) = entry {
-                    let metadata = entry.metadata().ok();
-                    let file_type = entry.file_type().ok();
-
-                    let name = entry.file_name().to_string_lossy().to_string();
-
-                    // Skip hidden files starting with '.'
-                    // if name.starts_with('.') {
-                    //     continue;
-                    // }
-
-                    let is_directory = file_type.as_ref().map(|ft| ft.is_dir()).unwrap_or(false);
-                    let is_symlink = file_type.as_ref().map(|ft| ft.is_symlink()).unwrap_or(false);
-                    let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-
-                    entries.push(FileEntry {
-                        name,
-                        path: entry.path().to_string_lossy().to_string(),
-                        is_directory,
-                        is_symlink,
-                        size,
-                    });
+            for entry in dir_entries.flatten() {
+                let metadata = entry.metadata().ok();
+                let file_type = entry.file_type().ok();
+
+                let name = entry.file_name().to_string_lossy().to_string();
+                let entry_path = dir_path.join(entry.file_name());
+
+                if options.hide_hidden && name.starts_with('.') {
+                    continue;
+                }
+
+                if exclude_set.is_match(&name) || exclude_set.is_match(&entry_path) {
+                    continue;
                 }
+
+                let is_directory = file_type.as_ref().map(|ft| ft.is_dir()).unwrap_or(false);
+                let is_symlink = file_type.as_ref().map(|ft| ft.is_symlink()).unwrap_or(false);
+                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+                let is_ignored = is_ignored(&ignore_chain, &entry.path(), is_directory);
+
+                if options.hide_ignored && is_ignored {
+                    continue;
+                }
+
+                entries.push(FileEntry {
+                    name,
+                    path: entry_path.to_string_lossy().to_string(),
+                    is_directory,
+                    is_symlink,
+                    size,
+                    is_ignored,
+                });
             }
         }
-        Err(e) => return Err(format!("Failed to read directory: {}", e)),
+        Err(e) => return Err(CommandError::io("read directory", e).with_path(dir_path)),
     }
 
-    // Sort: directories first, then alphabetically (case-insensitive)
-    entries.sort_by(|a, b| {
-        match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        }
-    });
+    entries.sort_by(|a, b| compare_entries(a.is_directory, &a.name, b.is_directory, &b.name));
 
     Ok(entries)
 }
 
+fn check_directory(path: &str) -> Result<PathBuf, CommandError> {
+    let resolved = workspace::check_path(path)?;
+
+    if !resolved.exists() {
+        return Err(CommandError::not_found(format!("Directory does not exist: {}", path), path));
+    }
+
+    if !resolved.is_dir() {
+        return Err(CommandError::invalid_input(format!("Path is not a directory: {}", path)).with_path(path));
+    }
+
+    Ok(resolved)
+}
+
+/// Lists a directory, directories first. Every entry is annotated with
+/// whether git would ignore it; `options` can additionally hide ignored
+/// entries, dotfiles, and anything matching the exclude globs (matched
+/// against both the entry name and its full path).
 #[tauri::command]
-pub fn read_file_content(path: String) -> Result<String, String> {
-    let file_path = Path::new(&path);
+pub fn read_directory(
+    path: String,
+    options: Option<ReadDirectoryOptions>,
+) -> Result<Vec<FileEntry>, CommandError> {
+    let resolved = check_directory(&path)?;
+    let options = options.unwrap_or_default();
+    let exclude_set = build_exclude_set(&options.exclude)?;
+
+    list_directory(Path::new(&path), &resolved, &options, &exclude_set)
+}
+
+// Entries per directory when read_directory_tree isn't given a limit
+const DEFAULT_TREE_PAGE_SIZE: usize = 1000;
+
+// Once a tree response holds this many entries, no further subdirectories are
+// loaded into it
+const MAX_TREE_ENTRIES: usize = 20_000;
+
+#[derive(Deserialize, Default)]
+pub struct DirectoryTreeOptions {
+    #[serde(flatten)]
+    pub filter: ReadDirectoryOptions,
+    /// Entries returned per directory; the rest can be fetched page by page
+    pub max_entries: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct DirectoryListing {
+    pub path: String,
+    pub entries: Vec<TreeEntry>,
+    pub total: usize, // Entries in the directory across all pages
+    /// Pass back as `page` to list the entries after these
+    pub next_page: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TreeEntry {
+    #[serde(flatten)]
+    pub entry: FileEntry,
+    /// Listing of a subdirectory within the requested depth. Symlinks and
+    /// ignored directories aren't descended into, and nothing more is loaded
+    /// once the response is full.
+    pub children: Option<DirectoryListing>,
+    /// Why a subdirectory within the depth couldn't be listed; the tree is
+    /// still returned, with no children for it
+    pub error: Option<CommandError>,
+}
+
+// A page token names the last entry returned, so paging stays in step even
+// if entries are added or removed between calls
+fn page_token(entry: &FileEntry) -> String {
+    format!("{}:{}", if entry.is_directory { "d" } else { "f" }, entry.name)
+}
+
+fn parse_page_token(token: &str) -> Result<(bool, &str), CommandError> {
+    match token.split_once(':') {
+        Some(("d", name)) => Ok((true, name)),
+        Some(("f", name)) => Ok((false, name)),
+        _ => Err(CommandError::invalid_input(format!("Invalid page token: {}", token))),
+    }
+}
+
+fn build_listing(
+    dir_path: &Path,
+    resolved: &Path,
+    depth: usize,
+    page: Option<&str>,
+    options: &DirectoryTreeOptions,
+    exclude_set: &GlobSet,
+    budget: &mut usize,
+) -> Result<DirectoryListing, CommandError> {
+    let entries = list_directory(dir_path, resolved, &options.filter, exclude_set)?;
+    let total = entries.len();
+
+    let start = match page {
+        Some(token) => {
+            let (is_dir, name) = parse_page_token(token)?;
+            entries
+                .iter()
+                .position(|e| compare_entries(e.is_directory, &e.name, is_dir, name).is_gt())
+                .unwrap_or(total)
+        }
+        None => 0,
+    };
+    let count = (total - start).min(options.max_entries.unwrap_or(DEFAULT_TREE_PAGE_SIZE).max(1));
+    *budget = budget.saturating_sub(count);
+
+    let next_page = (start + count < total).then(|| page_token(&entries[start + count - 1]));
+
+    let mut page_entries = Vec::with_capacity(count);
+    for entry in entries.into_iter().skip(start).take(count) {
+        let expand = depth > 1 && entry.is_directory && !entry.is_symlink && !entry.is_ignored && *budget > 0;
+        // A subdirectory that can't be read is listed without children rather than failing the tree
+        let (children, error) = if expand {
+            let child = resolved.join(&entry.name);
+            match build_listing(Path::new(&entry.path), &child, depth - 1, None, options, exclude_set, budget) {
+                Ok(listing) => (Some(listing), None),
+                Err(e) => (None, Some(e)),
+            }
+        } else {
+            (None, None)
+        };
+        page_entries.push(TreeEntry { entry, children, error });
+    }
+
+    Ok(DirectoryListing {
+        path: dir_path.to_string_lossy().to_string(),
+        entries: page_entries,
+        total,
+        next_page,
+    })
+}
+
+/// Lists a directory and its subdirectories `depth` levels deep (1 lists
+/// just `path`) in one call. Each directory returns at most `max_entries`
+/// entries; pass a listing's `next_page` back as `page`, with the same
+/// options, to continue listing `path` from there.
+#[tauri::command]
+pub fn read_directory_tree(
+    path: String,
+    depth: usize,
+    page: Option<String>,
+    options: Option<DirectoryTreeOptions>,
+) -> Result<DirectoryListing, CommandError> {
+    let resolved = check_directory(&path)?;
+    let options = options.unwrap_or_default();
+    let exclude_set = build_exclude_set(&options.filter.exclude)?;
+    let mut budget = MAX_TREE_ENTRIES;
+    let page = page.as_deref();
+
+    build_listing(Path::new(&path), &resolved, depth.max(1), page, &options, &exclude_set, &mut budget)
+}
+
+#[tauri::command]
+pub fn read_file_content(path: String) -> Result<String, CommandError> {
+    read_text_file(path).map(|file| file.content)
+}
+
+#[tauri::command]
+pub fn read_text_file(path: String) -> Result<TextFile, CommandError> {
+    let file_path = workspace::check_path(&path)?;
 
     if !file_path.exists() {
-        return Err(format!("File does not exist: {}", path));
+        return Err(CommandError::not_found(format!("File does not exist: {}", path), &path));
     }
 
     if !file_path.is_file() {
-        return Err(format!("Path is not a file: {}", path));
+        return Err(CommandError::invalid_input(format!("Path is not a file: {}", path)).with_path(&path));
     }
 
     // Check file size
-    let metadata = fs::metadata(&path).map_err(|e| format!("Failed to get file metadata: {}", e))?;
+    let metadata =
+        fs::metadata(&file_path).map_err(|e| CommandError::io("get file metadata", e).with_path(&path))?;
     let size = metadata.len();
 
-    // Refuse files over 50MB
+    // Refuse files over 50MB; those can be viewed with open_large_file
     if size > 50 * 1024 * 1024 {
-        return Err("File is too large (>50MB)".to_string());
+        let message = "File is too large (>50MB), open it in large file mode";
+        return Err(CommandError::new(ErrorKind::TooLarge, message).with_path(&path));
     }
 
-    // Read file content
-    match fs::read(&path) {
-        Ok(bytes) => {
-            // Check if file is binary (contains null bytes in first 8KB)
-            let check_bytes = &bytes[..std::cmp::min(bytes.len(), 8192)];
-            if check_bytes.contains(&0) {
-                return Err("Binary file cannot be displayed".to_string());
-            }
+    let bytes = fs::read(&file_path).map_err(|e| CommandError::io("read file", e).with_path(&path))?;
+    let fingerprint = fingerprint(&metadata, &bytes);
+    let decoded = encoding::decode(&bytes).map_err(|e| e.with_path(&path))?;
 
-            // Try to decode as UTF-8
-            match String::from_utf8(bytes) {
-                Ok(content) => Ok(content),
-                Err(_) => Err("File is not valid UTF-8 text".to_string()),
-            }
-        }
-        Err(e) => Err(format!("Failed to read file: {}", e)),
-    }
+    Ok(TextFile {
+        line_ending: detect_line_ending(&decoded.text),
+        has_final_newline: decoded.text.ends_with('\n'),
+        content: decoded.text,
+        fingerprint,
+        encoding: decoded.encoding,
+        has_bom: decoded.has_bom,
+    })
 }
 
+/// Writes `content` to `path` and returns the fingerprint of the saved file.
+/// If `expected_fingerprint` is given and the file on disk has changed since
+/// it was read, nothing is written and a `ModifiedOnDisk` error carrying the
+/// current fingerprint is returned.
+/// The content is saved as UTF-8 unless another `encoding` is given; pass the
+/// encoding and `with_bom` from `read_text_file` to keep the file's format.
+/// `format` normalizes line endings and trailing newlines before saving.
+/// The previous content is kept in the local history (see `history`).
 #[tauri::command]
-pub fn write_file_content(path: String, content: String) -> Result<(), String> {
-    fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))
+pub fn write_file_content(
+    path: String,
+    content: String,
+    expected_fingerprint: Option<FileFingerprint>,
+    encoding: Option<String>,
+    with_bom: Option<bool>,
+    format: Option<SaveFormat>,
+) -> Result<FileFingerprint, CommandError> {
+    let resolved = workspace::check_path(&path)?;
+
+    let content = match format {
+        Some(format) => text_format::normalize(&content, &format),
+        None => content,
+    };
+
+    let bytes = encoding::encode(
+        &content,
+        encoding.as_deref().unwrap_or("utf-8"),
+        with_bom.unwrap_or(false),
+    )
+    .map_err(|e| e.with_path(&path))?;
+
+    save_file(&resolved, &bytes, expected_fingerprint.as_ref())
 }
 
 #[tauri::command]
-pub fn create_file(path: String) -> Result<(), String> {
-    let file_path = Path::new(&path);
+pub fn create_file(path: String) -> Result<(), CommandError> {
+    let file_path = workspace::check_entry(&path)?;
 
     if file_path.exists() {
-        return Err(format!("File already exists: {}", path));
+        return Err(CommandError::already_exists(format!("File already exists: {}", path), &path));
     }
 
     // Ensure parent directory exists
+    let created_parents = journal::missing_parents(&file_path);
     if let Some(parent) = file_path.parent() {
         if !parent.exists() {
-            fs::create_dir_all(parent).map_err(|e| format!("Failed to create parent directory: {}", e))?;
+            fs::create_dir_all(parent)
+                .map_err(|e| CommandError::io("create parent directory", e).with_path(parent))?;
         }
     }
 
-    fs::write(&path, "").map_err(|e| format!("Failed to create file: {}", e))
+    fs::write(&file_path, "").map_err(|e| CommandError::io("create file", e).with_path(&path))?;
+
+    journal::record(FileOperation::Create {
+        path: file_path.to_string_lossy().to_string(),
+        is_directory: false,
+        created_parents,
+    });
+    Ok(())
 }
 
 #[tauri::command]
-pub fn create_directory(path: String) -> Result<(), String> {
-    let dir_path = Path::new(&path);
+pub fn create_directory(path: String) -> Result<(), CommandError> {
+    let dir_path = workspace::check_entry(&path)?;
 
     if dir_path.exists() {
-        return Err(format!("Directory already exists: {}", path));
+        return Err(CommandError::already_exists(format!("Directory already exists: {}", path), &path));
     }
 
-    fs::create_dir_all(&path).map_err(|e| format!("Failed to create directory: {}", e))
+    let created_parents = journal::missing_parents(&dir_path);
+    fs::create_dir_all(&dir_path).map_err(|e| CommandError::io("create directory", e).with_path(&path))?;
+
+    journal::record(FileOperation::Create {
+        path: dir_path.to_string_lossy().to_string(),
+        is_directory: true,
+        created_parents,
+    });
+    Ok(())
 }
 
 #[tauri::command]
-pub fn rename_item(old_path: String, new_path: String) -> Result<(), String> {
-    let old = Path::new(&old_path);
-    let new = Path::new(&new_path);
+pub fn rename_item(old_path: String, new_path: String) -> Result<(), CommandError> {
+    let old = workspace::check_entry(&old_path)?;
+    let new = workspace::check_entry(&new_path)?;
 
     if !old.exists() {
-        return Err(format!("Path does not exist: {}", old_path));
+        return Err(CommandError::not_found(format!("Path does not exist: {}", old_path), &old_path));
     }
 
     if new.exists() {
-        return Err(format!("Destination already exists: {}", new_path));
+        return Err(CommandError::already_exists(format!("Destination already exists: {}", new_path), &new_path));
+    }
+
+    move_path(&old, &new, &mut |_, _| {})?;
+
+    journal::record(FileOperation::Rename {
+        from: old.to_string_lossy().to_string(),
+        to: new.to_string_lossy().to_string(),
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Copy and move
+// ============================================================================
+
+// Progress updates are sent at most this often during copies
+const PROGRESS_INTERVAL_MS: u64 = 100;
+
+#[derive(Serialize, Clone)]
+pub struct FileOperationProgress {
+    pub operation_id: String,
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub current_path: String,
+}
+
+fn is_cross_device(e: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    let code = libc::EXDEV;
+
+    // ERROR_NOT_SAME_DEVICE
+    #[cfg(windows)]
+    let code = 17;
+
+    e.raw_os_error() == Some(code)
+}
+
+// Counts files and bytes below `path` so progress can be reported as a fraction
+fn tree_size(path: &Path) -> (u64, u64) {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return (0, 0),
+    };
+
+    if !metadata.is_dir() {
+        return (1, metadata.len());
+    }
+
+    fs::read_dir(path)
+        .map(|entries| {
+            entries.flatten().fold((0, 0), |(files, bytes), entry| {
+                let (f, b) = tree_size(&entry.path());
+                (files + f, bytes + b)
+            })
+        })
+        .unwrap_or((0, 0))
+}
+
+fn copy_error(action: &str, path: &Path, e: std::io::Error) -> CommandError {
+    CommandError::io(&format!("{} {}", action, path.display()), e).with_path(path)
+}
+
+// Recursively copies `source` to `destination`, keeping symlinks as links.
+// `on_file` is called with the size of every file or link copied.
+fn copy_recursive(
+    source: &Path,
+    destination: &Path,
+    on_file: &mut dyn FnMut(u64, &Path),
+) -> Result<(), CommandError> {
+    let metadata = fs::symlink_metadata(source).map_err(|e| copy_error("read", source, e))?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_dir() {
+        fs::create_dir(destination).map_err(|e| copy_error("create directory", destination, e))?;
+
+        let entries = fs::read_dir(source).map_err(|e| copy_error("read directory", source, e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| copy_error("read directory", source, e))?;
+            copy_recursive(&entry.path(), &destination.join(entry.file_name()), on_file)?;
+        }
+
+        // Applied last so read-only directories can still be filled
+        let _ = fs::set_permissions(destination, metadata.permissions());
+    } else if file_type.is_symlink() {
+        let target = fs::read_link(source).map_err(|e| copy_error("read link", source, e))?;
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, destination).map_err(|e| copy_error("create link", destination, e))?;
+
+        #[cfg(windows)]
+        {
+            let result = if fs::metadata(source).map(|m| m.is_dir()).unwrap_or(false) {
+                std::os::windows::fs::symlink_dir(&target, destination)
+            } else {
+                std::os::windows::fs::symlink_file(&target, destination)
+            };
+            result.map_err(|e| copy_error("create link", destination, e))?;
+        }
+
+        on_file(0, source);
+    } else {
+        // fs::copy carries the permission bits over
+        fs::copy(source, destination).map_err(|e| copy_error("copy", source, e))?;
+        on_file(metadata.len(), source);
+    }
+
+    Ok(())
+}
+
+// Renames `source` to `destination`, falling back to copy-then-delete when
+// they are on different filesystems. A failed fallback copy is cleaned up so
+// the source is never lost.
+pub fn move_path(
+    source: &Path,
+    destination: &Path,
+    on_file: &mut dyn FnMut(u64, &Path),
+) -> Result<(), CommandError> {
+    match fs::rename(source, destination) {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device(&e) => {
+            if let Err(e) = copy_recursive(source, destination, on_file) {
+                let _ = if destination.is_dir() {
+                    fs::remove_dir_all(destination)
+                } else {
+                    fs::remove_file(destination)
+                };
+                return Err(e);
+            }
+
+            if fs::symlink_metadata(source).map(|m| m.is_dir()).unwrap_or(false) {
+                fs::remove_dir_all(source)
+            } else {
+                fs::remove_file(source)
+            }
+            .map_err(|e| CommandError::io("remove source after copying to destination", e).with_path(source))
+        }
+        Err(e) => Err(CommandError::io("move", e).with_path(source)),
+    }
+}
+
+/// Picks a free name for copying `source` into `destination_dir`: the
+/// original name if it's free, then "name copy.ext", "name copy 2.ext", ...
+pub fn unique_copy_path(source: &Path, destination_dir: &Path) -> PathBuf {
+    let name = source.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let candidate = destination_dir.join(&name);
+    if fs::symlink_metadata(&candidate).is_err() {
+        return candidate;
+    }
+
+    // Dotfiles and directories keep their whole name as the stem
+    let is_dir = fs::symlink_metadata(source).map(|m| m.is_dir()).unwrap_or(false);
+    let (stem, ext) = match name.rfind('.') {
+        Some(i) if i > 0 && !is_dir => (name[..i].to_string(), name[i..].to_string()),
+        _ => (name.clone(), String::new()),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let candidate_name = if attempt == 1 {
+            format!("{} copy{}", stem, ext)
+        } else {
+            format!("{} copy {}{}", stem, attempt, ext)
+        };
+        let candidate = destination_dir.join(candidate_name);
+        if fs::symlink_metadata(&candidate).is_err() {
+            return candidate;
+        }
+        attempt += 1;
+    }
+}
+
+// Wraps a callback that emits throttled progress events for a tree operation
+fn progress_reporter(app: AppHandle, operation_id: Option<String>, source: &Path) -> impl FnMut(u64, &Path) {
+    let (files_total, bytes_total) = if operation_id.is_some() { tree_size(source) } else { (0, 0) };
+    let mut files_done = 0;
+    let mut bytes_done = 0;
+    let mut last_emit = Instant::now();
+
+    move |bytes: u64, current: &Path| {
+        let Some(operation_id) = &operation_id else {
+            return;
+        };
+
+        files_done += 1;
+        bytes_done += bytes;
+
+        if files_done == files_total || last_emit.elapsed() >= Duration::from_millis(PROGRESS_INTERVAL_MS) {
+            let _ = app.emit(
+                &format!("file-operation-progress-{}", operation_id),
+                FileOperationProgress {
+                    operation_id: operation_id.clone(),
+                    files_done,
+                    files_total,
+                    bytes_done,
+                    bytes_total,
+                    current_path: current.to_string_lossy().to_string(),
+                },
+            );
+            last_emit = Instant::now();
+        }
+    }
+}
+
+fn check_not_into_itself(source: &Path, destination_dir: &Path) -> Result<(), CommandError> {
+    let source = fs::canonicalize(source).map_err(|e| CommandError::io("resolve path", e).with_path(source))?;
+    let destination_dir = fs::canonicalize(destination_dir)
+        .map_err(|e| CommandError::io("resolve path", e).with_path(destination_dir))?;
+
+    if destination_dir.starts_with(&source) {
+        return Err(CommandError::invalid_input("Cannot copy or move a folder into itself"));
+    }
+
+    Ok(())
+}
+
+/// Copies a file or directory tree into `destination_dir`, picking a
+/// "copy" name if the original name is taken. If `operation_id` is given,
+/// progress is emitted as `file-operation-progress-{operation_id}`.
+/// Returns the path of the new copy.
+#[tauri::command(async)]
+pub fn copy_item(
+    app: AppHandle,
+    source_path: String,
+    destination_dir: String,
+    operation_id: Option<String>,
+) -> Result<String, CommandError> {
+    let source = workspace::check_path(&source_path)?;
+    let destination_dir = workspace::check_path(&destination_dir)?;
+
+    if fs::symlink_metadata(&source).is_err() {
+        return Err(CommandError::not_found(format!("Path does not exist: {}", source_path), &source_path));
+    }
+
+    if !destination_dir.is_dir() {
+        let message = format!("Path is not a directory: {}", destination_dir.display());
+        return Err(CommandError::invalid_input(message).with_path(&destination_dir));
+    }
+
+    if source.is_dir() {
+        check_not_into_itself(&source, &destination_dir)?;
+    }
+
+    let destination = unique_copy_path(&source, &destination_dir);
+    let mut on_file = progress_reporter(app, operation_id, &source);
+
+    if let Err(e) = copy_recursive(&source, &destination, &mut on_file) {
+        // Don't leave a partial copy behind
+        let _ = if destination.is_dir() {
+            fs::remove_dir_all(&destination)
+        } else {
+            fs::remove_file(&destination)
+        };
+        return Err(e);
     }
 
-    fs::rename(&old_path, &new_path).map_err(|e| format!("Failed to rename: {}", e))
+    let destination = destination.to_string_lossy().to_string();
+    journal::record(FileOperation::Copy { path: destination.clone() });
+    Ok(destination)
 }
 
+/// Copies an item next to itself ("name copy.ext").
+#[tauri::command(async)]
+pub fn duplicate_item(
+    app: AppHandle,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<String, CommandError> {
+    let parent = Path::new(&path)
+        .parent()
+        .ok_or_else(|| CommandError::invalid_input(format!("Cannot duplicate: {}", path)))?
+        .to_string_lossy()
+        .to_string();
+
+    copy_item(app, path, parent, operation_id)
+}
+
+/// Moves a file or directory tree into `destination_dir`, across
+/// filesystems if needed. Returns the new path.
+#[tauri::command(async)]
+pub fn move_item(
+    app: AppHandle,
+    source_path: String,
+    destination_dir: String,
+    operation_id: Option<String>,
+) -> Result<String, CommandError> {
+    let source = workspace::check_entry(&source_path)?;
+    let destination_dir = workspace::check_path(&destination_dir)?;
+
+    if fs::symlink_metadata(&source).is_err() {
+        return Err(CommandError::not_found(format!("Path does not exist: {}", source_path), &source_path));
+    }
+
+    if !destination_dir.is_dir() {
+        let message = format!("Path is not a directory: {}", destination_dir.display());
+        return Err(CommandError::invalid_input(message).with_path(&destination_dir));
+    }
+
+    if source.is_dir() {
+        check_not_into_itself(&source, &destination_dir)?;
+    }
+
+    let name = source
+        .file_name()
+        .ok_or_else(|| CommandError::invalid_input(format!("Cannot move: {}", source_path)))?;
+    let destination = destination_dir.join(name);
+
+    if fs::symlink_metadata(&destination).is_ok() {
+        let message = format!("Destination already exists: {}", destination.display());
+        return Err(CommandError::already_exists(message, &destination));
+    }
+
+    let mut on_file = progress_reporter(app, operation_id, &source);
+    move_path(&source, &destination, &mut on_file)?;
+
+    let destination = destination.to_string_lossy().to_string();
+    journal::record(FileOperation::Rename {
+        from: source.to_string_lossy().to_string(),
+        to: destination.clone(),
+    });
+    Ok(destination)
+}
+
+/// Moves `path` to the system trash. The returned handle's `id` can be passed to
+/// `restore_from_trash` to undo the delete.
+#[tauri::command]
+pub fn delete_item(path: String) -> Result<TrashHandle, CommandError> {
+    let item_path = workspace::check_entry(&path)?;
+
+    if fs::symlink_metadata(&item_path).is_err() {
+        return Err(CommandError::not_found(format!("Path does not exist: {}", path), &path));
+    }
+
+    let handle = move_to_trash(&item_path)?;
+
+    journal::record(FileOperation::Delete { handle: handle.clone() });
+    Ok(handle)
+}
+
+/// Deletes `path` for good, bypassing the trash.
 #[tauri::command]
-pub fn delete_item(path: String) -> Result<(), String> {
-    let item_path = Path::new(&path);
+pub fn delete_item_permanently(path: String) -> Result<(), CommandError> {
+    let item_path = workspace::check_entry(&path)?;
 
     if !item_path.exists() {
-        return Err(format!("Path does not exist: {}", path));
+        return Err(CommandError::not_found(format!("Path does not exist: {}", path), &path));
     }
 
     if item_path.is_dir() {
-        fs::remove_dir_all(&path).map_err(|e| format!("Failed to delete directory: {}", e))
+        fs::remove_dir_all(&item_path).map_err(|e| CommandError::io("delete directory", e).with_path(&path))
     } else {
-        fs::remove_file(&path).map_err(|e| format!("Failed to delete file: {}", e))
+        fs::remove_file(&item_path).map_err(|e| CommandError::io("delete file", e).with_path(&path))
     }
 }
 
 #[tauri::command]
-pub fn get_home_directory() -> Result<String, String> {
+pub fn get_home_directory() -> Result<String, CommandError> {
     dirs::home_dir()
         .map(|p| p.to_string_lossy().to_string())
-        .ok_or_else(|| "Could not determine home directory".to_string())
+        .ok_or_else(|| CommandError::new(ErrorKind::NotFound, "Could not determine home directory"))
 }
 
 #[tauri::command]
 pub fn file_exists(path: String) -> bool {
-    Path::new(&path).exists()
+    workspace::check_path(&path).map(|resolved| resolved.exists()).unwrap_or(false)
 }
 
+fn to_ms(time: std::io::Result<std::time::SystemTime>) -> Option<u64> {
+    time.ok()?.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64)
+}
+
+#[cfg(unix)]
+fn ctime_ms(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    u64::try_from(metadata.ctime() * 1000 + metadata.ctime_nsec() / 1_000_000).ok()
+}
+
+#[cfg(not(unix))]
+fn ctime_ms(metadata: &fs::Metadata) -> Option<u64> {
+    to_ms(metadata.created())
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+// User name of the file's owner, or the numeric uid if it has no passwd entry
+#[cfg(unix)]
+fn file_owner(metadata: &fs::Metadata) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = metadata.uid();
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+
+    let status = unsafe { libc::getpwuid_r(uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return Some(uid.to_string());
+    }
+
+    let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+    Some(name.to_string_lossy().to_string())
+}
+
+#[cfg(not(unix))]
+fn file_owner(_metadata: &fs::Metadata) -> Option<String> {
+    None
+}
+
+// Reads at most `limit` bytes from the start of a file
+fn read_prefix(path: &Path, limit: u64) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut prefix = Vec::new();
+    fs::File::open(path)?.take(limit).read_to_end(&mut prefix)?;
+    Ok(prefix)
+}
+
+/// Metadata for the status bar and properties dialog. Content-based fields
+/// (binary, MIME type, encoding, line endings) only look at the first 64KB.
 #[tauri::command]
-pub fn get_file_info(path: String) -> Result<FileInfo, String> {
-    let file_path = Path::new(&path);
+pub fn get_file_info(path: String) -> Result<FileInfo, CommandError> {
+    let file_path = workspace::check_path(&path)?;
 
     if !file_path.exists() {
-        return Err(format!("File does not exist: {}", path));
+        return Err(CommandError::not_found(format!("File does not exist: {}", path), &path));
     }
 
-    let metadata = fs::metadata(&path).map_err(|e| format!("Failed to get metadata: {}", e))?;
+    let metadata =
+        fs::metadata(&file_path).map_err(|e| CommandError::io("get metadata", e).with_path(&path))?;
 
-    let is_binary = if file_path.is_file() {
-        // Check first 8KB for null bytes
-        if let Ok(bytes) = fs::read(&path) {
-            let check_bytes = &bytes[..std::cmp::min(bytes.len(), 8192)];
-            check_bytes.contains(&0)
-        } else {
-            false
-        }
-    } else {
-        false
-    };
+    // Only the link itself is looked at here; everything else comes from the
+    // checked target
+    let symlink_target = fs::symlink_metadata(&path)
+        .ok()
+        .filter(|m| m.file_type().is_symlink())
+        .and_then(|_| fs::read_link(&path).ok())
+        .map(|target| target.to_string_lossy().to_string());
 
-    Ok(FileInfo {
+    let mut info = FileInfo {
         size: metadata.len(),
-        is_binary,
+        is_binary: false,
         is_readonly: metadata.permissions().readonly(),
-    })
+        mtime_ms: to_ms(metadata.modified()),
+        ctime_ms: ctime_ms(&metadata),
+        mode: file_mode(&metadata),
+        owner: file_owner(&metadata),
+        symlink_target,
+        mime: None,
+        encoding: None,
+        line_ending: None,
+    };
+
+    if !metadata.is_file() {
+        return Ok(info);
+    }
+
+    let prefix = read_prefix(&file_path, FILE_INFO_PREFIX_LEN)
+        .map_err(|e| CommandError::io("read file", e).with_path(&path))?;
+    let detected = encoding::detect_encoding(&prefix, prefix.len() as u64 == metadata.len());
+    let extension_mime = mime_guess::from_path(&path).first_raw();
+
+    info.is_binary = detected.is_none();
+    info.mime = match (preview::sniff_image(&prefix), detected) {
+        (Some((mime, _)), _) => Some(mime),
+        (None, _) if extension_mime.is_some() => extension_mime,
+        (None, Some(_)) => Some("text/plain"),
+        (None, None) => Some("application/octet-stream"),
+    }
+    .map(String::from);
+
+    if let Some((text_encoding, bom_len)) = detected {
+        let (text, _) = text_encoding.decode_without_bom_handling(&prefix[bom_len..]);
+        info.encoding = Some(text_encoding.name().to_string());
+        info.line_ending = detect_line_ending(&text);
+    }
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_directory: bool) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            path: format!("/root/{}", name),
+            is_directory,
+            is_symlink: false,
+            size: 0,
+            is_ignored: false,
+        }
+    }
+
+    #[test]
+    fn page_token_round_trips() {
+        let token = page_token(&entry("src", true));
+        assert_eq!(token, "d:src");
+        assert_eq!(parse_page_token(&token).unwrap(), (true, "src"));
+
+        let token = page_token(&entry("main.rs", false));
+        assert_eq!(parse_page_token(&token).unwrap(), (false, "main.rs"));
+    }
+
+    #[test]
+    fn page_token_keeps_colons_in_names() {
+        let token = page_token(&entry("a:b:c.txt", false));
+        assert_eq!(parse_page_token(&token).unwrap(), (false, "a:b:c.txt"));
+    }
+
+    #[test]
+    fn invalid_page_token_is_rejected() {
+        for token in ["", "src", "x:src", "D:src"] {
+            let error = parse_page_token(token).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn entries_sort_directories_first_then_by_name() {
+        use std::cmp::Ordering;
+
+        assert_eq!(compare_entries(true, "z", false, "a"), Ordering::Less);
+        assert_eq!(compare_entries(false, "apple", false, "Banana"), Ordering::Less);
+        assert_eq!(compare_entries(false, "B", false, "b"), Ordering::Less);
+    }
 }
@@ -2,6 +2,9 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::process::Command;
 
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
 // ============================================================================
 // Data Structures
 // ============================================================================
@@ -47,27 +50,41 @@ pub struct GitBranches {
 // Helper Functions
 // ============================================================================
 
-fn run_git_command(root_path: &str, args: &[&str]) -> Result<String, String> {
+fn run_git_command(root_path: &Path, args: &[&str]) -> Result<String, CommandError> {
     let output = Command::new("git")
         .args(args)
         .current_dir(root_path)
         .output()
-        .map_err(|e| format!("Failed to execute git: {}", e))?;
+        .map_err(|e| CommandError::io("execute git", e))?;
 
     if output.status.success() {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        Err(if stderr.is_empty() {
+        let message = if stderr.is_empty() {
             "Git command failed".to_string()
         } else {
             stderr.trim().to_string()
-        })
+        };
+        Err(CommandError::new(ErrorKind::Git, message).with_path(root_path))
     }
 }
 
-fn is_git_repo(root_path: &str) -> bool {
-    Path::new(root_path).join(".git").exists()
+fn is_git_repo(root_path: &Path) -> bool {
+    root_path.join(".git").exists()
+}
+
+// Pathspecs from the webview are checked like any other path and passed to
+// git as checked absolute paths. Only their directories are resolved, so
+// symlinks are staged or restored as the links git tracks.
+fn check_pathspecs(root: &Path, paths: &[String]) -> Result<Vec<String>, CommandError> {
+    paths
+        .iter()
+        .map(|path| {
+            let checked = workspace::check_entry(&root.join(path).to_string_lossy())?;
+            Ok(checked.to_string_lossy().to_string())
+        })
+        .collect()
 }
 
 // ============================================================================
@@ -75,14 +92,16 @@ fn is_git_repo(root_path: &str) -> bool {
 // ============================================================================
 
 #[tauri::command]
-pub fn get_git_branch(root_path: &str) -> Result<Option<String>, String> {
-    let git_head = Path::new(root_path).join(".git/HEAD");
+pub fn get_git_branch(root_path: &str) -> Result<Option<String>, CommandError> {
+    let root = workspace::check_path(root_path)?;
+    let git_head = root.join(".git/HEAD");
 
     if !git_head.exists() {
         return Ok(None);
     }
 
-    let content = std::fs::read_to_string(&git_head).map_err(|e| e.to_string())?;
+    let content =
+        std::fs::read_to_string(&git_head).map_err(|e| CommandError::io("read HEAD", e).with_path(&git_head))?;
 
     if let Some(branch) = content.strip_prefix("ref: refs/heads/") {
         Ok(Some(branch.trim().to_string()))
@@ -92,12 +111,13 @@ pub fn get_git_branch(root_path: &str) -> Result<Option<String>, String> {
 }
 
 #[tauri::command]
-pub fn git_status(root_path: &str) -> Result<Option<GitStatus>, String> {
-    if !is_git_repo(root_path) {
+pub fn git_status(root_path: &str) -> Result<Option<GitStatus>, CommandError> {
+    let root = workspace::check_path(root_path)?;
+    if !is_git_repo(&root) {
         return Ok(None);
     }
 
-    let output = run_git_command(root_path, &["status", "--porcelain=v1"])?;
+    let output = run_git_command(&root, &["status", "--porcelain=v1"])?;
 
     let mut staged = Vec::new();
     let mut unstaged = Vec::new();
@@ -156,12 +176,13 @@ pub fn git_status(root_path: &str) -> Result<Option<GitStatus>, String> {
 }
 
 #[tauri::command]
-pub fn git_branches(root_path: &str) -> Result<Option<GitBranches>, String> {
-    if !is_git_repo(root_path) {
+pub fn git_branches(root_path: &str) -> Result<Option<GitBranches>, CommandError> {
+    let root = workspace::check_path(root_path)?;
+    if !is_git_repo(&root) {
         return Ok(None);
     }
 
-    let output = run_git_command(root_path, &["branch", "-a", "--no-color"])?;
+    let output = run_git_command(&root, &["branch", "-a", "--no-color"])?;
 
     let mut current = String::new();
     let mut local = Vec::new();
@@ -203,8 +224,9 @@ pub fn git_branches(root_path: &str) -> Result<Option<GitBranches>, String> {
 }
 
 #[tauri::command]
-pub fn git_log(root_path: &str, limit: Option<u32>) -> Result<Vec<GitCommit>, String> {
-    if !is_git_repo(root_path) {
+pub fn git_log(root_path: &str, limit: Option<u32>) -> Result<Vec<GitCommit>, CommandError> {
+    let root = workspace::check_path(root_path)?;
+    if !is_git_repo(&root) {
         return Ok(Vec::new());
     }
 
@@ -212,7 +234,7 @@ pub fn git_log(root_path: &str, limit: Option<u32>) -> Result<Vec<GitCommit>, St
     let format = "%h|%s|%an|%ar";
 
     let output = run_git_command(
-        root_path,
+        &root,
         &["log", &format!("--format={}", format), "-n", &limit_str],
     )?;
 
@@ -237,74 +259,84 @@ pub fn git_log(root_path: &str, limit: Option<u32>) -> Result<Vec<GitCommit>, St
 }
 
 #[tauri::command]
-pub fn git_stage(root_path: &str, paths: Vec<String>) -> Result<(), String> {
+pub fn git_stage(root_path: &str, paths: Vec<String>) -> Result<(), CommandError> {
     if paths.is_empty() {
         return Ok(());
     }
 
+    let root = workspace::check_path(root_path)?;
+    let paths = check_pathspecs(&root, &paths)?;
     let mut args = vec!["add", "--"];
     let path_refs: Vec<&str> = paths.iter().map(|s| s.as_str()).collect();
     args.extend(path_refs);
 
-    run_git_command(root_path, &args)?;
+    run_git_command(&root, &args)?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn git_stage_all(root_path: &str) -> Result<(), String> {
-    run_git_command(root_path, &["add", "-A"])?;
+pub fn git_stage_all(root_path: &str) -> Result<(), CommandError> {
+    let root = workspace::check_path(root_path)?;
+    run_git_command(&root, &["add", "-A"])?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn git_unstage(root_path: &str, paths: Vec<String>) -> Result<(), String> {
+pub fn git_unstage(root_path: &str, paths: Vec<String>) -> Result<(), CommandError> {
     if paths.is_empty() {
         return Ok(());
     }
 
+    let root = workspace::check_path(root_path)?;
+    let paths = check_pathspecs(&root, &paths)?;
     let mut args = vec!["restore", "--staged", "--"];
     let path_refs: Vec<&str> = paths.iter().map(|s| s.as_str()).collect();
     args.extend(path_refs);
 
-    run_git_command(root_path, &args)?;
+    run_git_command(&root, &args)?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn git_unstage_all(root_path: &str) -> Result<(), String> {
-    run_git_command(root_path, &["restore", "--staged", "."])?;
+pub fn git_unstage_all(root_path: &str) -> Result<(), CommandError> {
+    let root = workspace::check_path(root_path)?;
+    run_git_command(&root, &["restore", "--staged", "."])?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn git_discard_changes(root_path: &str, paths: Vec<String>) -> Result<(), String> {
+pub fn git_discard_changes(root_path: &str, paths: Vec<String>) -> Result<(), CommandError> {
     if paths.is_empty() {
         return Ok(());
     }
 
+    let root = workspace::check_path(root_path)?;
+    let paths = check_pathspecs(&root, &paths)?;
     let mut args = vec!["restore", "--"];
     let path_refs: Vec<&str> = paths.iter().map(|s| s.as_str()).collect();
     args.extend(path_refs);
 
-    run_git_command(root_path, &args)?;
+    run_git_command(&root, &args)?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn git_commit(root_path: &str, message: &str) -> Result<String, String> {
+pub fn git_commit(root_path: &str, message: &str) -> Result<String, CommandError> {
+    let root = workspace::check_path(root_path)?;
     if message.trim().is_empty() {
-        return Err("Commit message cannot be empty".to_string());
+        return Err(CommandError::invalid_input("Commit message cannot be empty"));
     }
 
-    run_git_command(root_path, &["commit", "-m", message])?;
+    run_git_command(&root, &["commit", "-m", message])?;
 
     // Get the commit hash
-    let hash = run_git_command(root_path, &["rev-parse", "--short", "HEAD"])?;
+    let hash = run_git_command(&root, &["rev-parse", "--short", "HEAD"])?;
     Ok(hash.trim().to_string())
 }
 
 #[tauri::command]
-pub fn git_checkout(root_path: &str, branch: &str) -> Result<(), String> {
+pub fn git_checkout(root_path: &str, branch: &str) -> Result<(), CommandError> {
+    let root = workspace::check_path(root_path)?;
     // Handle remote branches by creating local tracking branch
     let branch_name = if branch.starts_with("origin/") {
         branch.strip_prefix("origin/").unwrap_or(branch)
@@ -312,20 +344,21 @@ pub fn git_checkout(root_path: &str, branch: &str) -> Result<(), String> {
         branch
     };
 
-    run_git_command(root_path, &["checkout", branch_name])?;
+    run_git_command(&root, &["checkout", branch_name])?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn git_create_branch(root_path: &str, branch: &str, checkout: bool) -> Result<(), String> {
+pub fn git_create_branch(root_path: &str, branch: &str, checkout: bool) -> Result<(), CommandError> {
+    let root = workspace::check_path(root_path)?;
     if branch.trim().is_empty() {
-        return Err("Branch name cannot be empty".to_string());
+        return Err(CommandError::invalid_input("Branch name cannot be empty"));
     }
 
     if checkout {
-        run_git_command(root_path, &["checkout", "-b", branch])?;
+        run_git_command(&root, &["checkout", "-b", branch])?;
     } else {
-        run_git_command(root_path, &["branch", branch])?;
+        run_git_command(&root, &["branch", branch])?;
     }
 
     Ok(())
@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::app_data::app_data_dir;
+use super::encoding;
+use super::filesystem::{atomic_write, fingerprint, FileFingerprint};
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+// Serializes access to the history store so concurrent saves can't clobber an index
+lazy_static::lazy_static! {
+    static ref HISTORY_LOCK: Arc<Mutex<()>> = Arc::new(Mutex::new(()));
+}
+
+// Snapshots recorded since the last sweep of the whole store
+static RECORDED_SINCE_SWEEP: AtomicUsize = AtomicUsize::new(0);
+
+// Retention per file: at most this many snapshots, none older than this
+const MAX_SNAPSHOTS_PER_FILE: usize = 50;
+const MAX_SNAPSHOT_AGE_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+
+// Bigger files aren't snapshotted, so a save never has to copy a huge file
+const MAX_SNAPSHOT_SIZE: u64 = 10 * 1024 * 1024;
+
+// Retention for the whole store, enforced by `sweep`: files that are never
+// saved or looked at again would otherwise keep their snapshots forever
+const MAX_HISTORY_BYTES: u64 = 500 * 1024 * 1024;
+const SWEEP_EVERY: usize = 100;
+
+/// What replaced the content kept in a snapshot.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotSource {
+    Save,
+    Restore,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub size: u64,
+    pub hash: String,
+    pub source: SnapshotSource,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct HistoryIndex {
+    path: String,
+    entries: Vec<HistoryEntry>, // Oldest first
+}
+
+#[derive(Serialize)]
+pub struct HistorySnapshot {
+    pub entry: HistoryEntry,
+    pub content: String,
+    pub encoding: String,
+    pub has_bom: bool,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Every file gets its own directory, named after a hash of its resolved path
+fn history_dir(path: &Path) -> Result<PathBuf, CommandError> {
+    let key = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+    Ok(app_data_dir()?.join("history").join(&key[..32]))
+}
+
+fn snapshot_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.snapshot", id))
+}
+
+// A missing or unreadable index just means there is no history yet
+fn load_index(dir: &Path) -> HistoryIndex {
+    fs::read(dir.join("index.json"))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(dir: &Path, index: &HistoryIndex) -> Result<(), CommandError> {
+    let json = serde_json::to_vec_pretty(index)
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to serialize history index: {}", e)))?;
+    atomic_write(&dir.join("index.json"), &json)
+}
+
+// Drops snapshots beyond the retention limits, oldest first. Returns whether
+// anything was dropped.
+fn prune(dir: &Path, index: &mut HistoryIndex) -> bool {
+    let cutoff = now_ms().saturating_sub(MAX_SNAPSHOT_AGE_MS);
+    let excess = index.entries.len().saturating_sub(MAX_SNAPSHOTS_PER_FILE);
+    let count = index.entries.len();
+
+    let mut position = 0;
+    index.entries.retain(|entry| {
+        let keep = position >= excess && entry.timestamp_ms >= cutoff;
+        if !keep {
+            let _ = fs::remove_file(snapshot_path(dir, &entry.id));
+        }
+        position += 1;
+        keep
+    });
+
+    index.entries.len() != count
+}
+
+// The index with expired snapshots dropped, so history that is only ever
+// looked at doesn't outlive the retention limits either
+fn load_pruned_index(dir: &Path) -> HistoryIndex {
+    let mut index = load_index(dir);
+
+    if prune(dir, &mut index) {
+        if let Err(e) = save_index(dir, &index) {
+            log::warn!("Failed to save pruned history index in {}: {}", dir.display(), e);
+        }
+    }
+
+    index
+}
+
+/// Keeps the current content of `path` (already resolved) as a snapshot,
+/// before something replaces it. Nothing is recorded if the file doesn't
+/// exist, is too large, or hasn't changed since the latest snapshot.
+pub fn record(path: &Path, source: SnapshotSource) -> Result<(), CommandError> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() && metadata.len() <= MAX_SNAPSHOT_SIZE => {}
+        _ => return Ok(()),
+    }
+
+    let bytes = fs::read(path).map_err(|e| CommandError::io("read file", e).with_path(path))?;
+
+    // Every so often the whole store is swept, in the background so the save doesn't wait on it
+    let stored = store_snapshot(path, &bytes, source)?;
+    if stored && RECORDED_SINCE_SWEEP.fetch_add(1, Ordering::Relaxed) + 1 >= SWEEP_EVERY {
+        RECORDED_SINCE_SWEEP.store(0, Ordering::Relaxed);
+        std::thread::spawn(sweep);
+    }
+
+    Ok(())
+}
+
+// Returns whether a new snapshot was written
+fn store_snapshot(path: &Path, bytes: &[u8], source: SnapshotSource) -> Result<bool, CommandError> {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    let dir = history_dir(path)?;
+    let mut index = load_index(&dir);
+
+    if index.entries.last().map(|e| e.hash == hash).unwrap_or(false) {
+        return Ok(false);
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| CommandError::io("create history directory", e).with_path(&dir))?;
+
+    // Strictly increasing, so IDs stay unique even for saves within the same millisecond
+    let timestamp_ms = index.entries.last().map_or(0, |e| e.timestamp_ms + 1).max(now_ms());
+    let id = format!("{}-{}", timestamp_ms, &hash[..8]);
+    fs::write(snapshot_path(&dir, &id), bytes)
+        .map_err(|e| CommandError::io("write snapshot", e).with_path(&dir))?;
+
+    index.path = path.to_string_lossy().to_string();
+    index.entries.push(HistoryEntry {
+        id,
+        timestamp_ms,
+        size: bytes.len() as u64,
+        hash,
+        source,
+    });
+    prune(&dir, &mut index);
+    save_index(&dir, &index)?;
+    Ok(true)
+}
+
+/// Applies the retention limits to every file in the history store, not just
+/// the ones saved or looked at lately. Directories left without snapshots are
+/// removed, then the oldest snapshots across all files are dropped until the
+/// store fits in `MAX_HISTORY_BYTES`. Runs on startup and every `SWEEP_EVERY`
+/// snapshots.
+pub fn sweep() {
+    let root = match app_data_dir() {
+        Ok(dir) => dir.join("history"),
+        Err(_) => return,
+    };
+
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    let dirs: Vec<PathBuf> = match fs::read_dir(&root) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect(),
+        Err(_) => return,
+    };
+
+    let mut indexes = Vec::new();
+    for dir in dirs {
+        let index = load_pruned_index(&dir);
+        if index.entries.is_empty() {
+            remove_history_dir(&dir);
+        } else {
+            indexes.push((dir, index));
+        }
+    }
+
+    let total: u64 = indexes.iter().flat_map(|(_, index)| &index.entries).map(|e| e.size).sum();
+    if total <= MAX_HISTORY_BYTES {
+        return;
+    }
+
+    // (timestamp, index position, size) for every snapshot, oldest first
+    let mut snapshots: Vec<(u64, usize, u64)> = indexes
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, index))| index.entries.iter().map(move |e| (e.timestamp_ms, i, e.size)))
+        .collect();
+    snapshots.sort_unstable();
+
+    // Entries are oldest first within each index, so dropping the oldest
+    // overall always drops from the front of one of them
+    let mut dropped = vec![0; indexes.len()];
+    let mut excess = total - MAX_HISTORY_BYTES;
+    for (_, i, size) in snapshots {
+        if excess == 0 {
+            break;
+        }
+        dropped[i] += 1;
+        excess = excess.saturating_sub(size);
+    }
+
+    for ((dir, mut index), count) in indexes.into_iter().zip(dropped) {
+        if count == 0 {
+            continue;
+        }
+        for entry in index.entries.drain(..count) {
+            let _ = fs::remove_file(snapshot_path(&dir, &entry.id));
+        }
+        if index.entries.is_empty() {
+            remove_history_dir(&dir);
+        } else if let Err(e) = save_index(&dir, &index) {
+            log::warn!("Failed to save pruned history index in {}: {}", dir.display(), e);
+        }
+    }
+}
+
+fn remove_history_dir(dir: &Path) {
+    if let Err(e) = fs::remove_dir_all(dir) {
+        log::warn!("Failed to remove history directory {}: {}", dir.display(), e);
+    }
+}
+
+// Looks `id` up in the index, so only real snapshot files can ever be read
+fn load_snapshot(path: &Path, id: &str) -> Result<(HistoryEntry, Vec<u8>), CommandError> {
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    let dir = history_dir(path)?;
+
+    let entry = load_pruned_index(&dir)
+        .entries
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| CommandError::not_found(format!("Snapshot not found: {}", id), path))?;
+
+    let bytes =
+        fs::read(snapshot_path(&dir, id)).map_err(|e| CommandError::io("read snapshot", e).with_path(path))?;
+    Ok((entry, bytes))
+}
+
+/// Lists the snapshots kept for `path`, newest first.
+#[tauri::command]
+pub fn list_file_history(path: String) -> Result<Vec<HistoryEntry>, CommandError> {
+    let resolved = workspace::check_path(&path)?;
+
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    let index = load_pruned_index(&history_dir(&resolved)?);
+
+    Ok(index.entries.into_iter().rev().collect())
+}
+
+/// Returns a snapshot decoded as text, e.g. to show it in a diff against the
+/// current content.
+#[tauri::command]
+pub fn read_history_snapshot(path: String, id: String) -> Result<HistorySnapshot, CommandError> {
+    let resolved = workspace::check_path(&path)?;
+    let (entry, bytes) = load_snapshot(&resolved, &id)?;
+    let decoded = encoding::decode(&bytes).map_err(|e| e.with_path(&path))?;
+
+    Ok(HistorySnapshot {
+        entry,
+        content: decoded.text,
+        encoding: decoded.encoding,
+        has_bom: decoded.has_bom,
+    })
+}
+
+/// Puts a snapshot's content back into the file and returns the new
+/// fingerprint. The content being replaced is snapshotted first, so a
+/// restore can itself be undone.
+#[tauri::command]
+pub fn restore_history_snapshot(path: String, id: String) -> Result<FileFingerprint, CommandError> {
+    let resolved = workspace::check_path(&path)?;
+    let (_, bytes) = load_snapshot(&resolved, &id)?;
+
+    record(&resolved, SnapshotSource::Restore)?;
+    atomic_write(&resolved, &bytes)?;
+
+    let metadata =
+        fs::metadata(&resolved).map_err(|e| CommandError::io("get file metadata", e).with_path(&path))?;
+    Ok(fingerprint(&metadata, &bytes))
+}
@@ -0,0 +1,253 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::filesystem::move_path;
+use super::trash::{move_to_trash, restore_trashed, TrashHandle};
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+// Global journal of undoable explorer operations
+lazy_static::lazy_static! {
+    static ref JOURNAL: Arc<Mutex<Journal>> = Arc::new(Mutex::new(Journal::default()));
+}
+
+// Oldest operations are forgotten beyond this many
+const MAX_UNDO: usize = 50;
+
+/// A completed explorer operation, with whatever is needed to reverse it.
+/// Paths are the resolved paths the operation acted on.
+#[derive(Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileOperation {
+    Create {
+        path: String,
+        is_directory: bool,
+        /// Missing parent folders created along with it, removed again on undo
+        created_parents: usize,
+    },
+    Rename { from: String, to: String },
+    Copy { path: String },
+    Delete { handle: TrashHandle },
+}
+
+impl FileOperation {
+    fn paths(&self) -> Vec<&str> {
+        match self {
+            FileOperation::Create { path, .. } | FileOperation::Copy { path } => vec![path],
+            FileOperation::Rename { from, to } => vec![from, to],
+            FileOperation::Delete { handle } => vec![&handle.original_path],
+        }
+    }
+}
+
+#[derive(Default)]
+struct Journal {
+    undo: VecDeque<FileOperation>,
+    // Undone operations plus the trash handle needed to redo them, if any
+    redo: Vec<(FileOperation, Option<TrashHandle>)>,
+}
+
+/// Records a successful operation so it can be undone. Any redo history is
+/// dropped, as it no longer applies.
+pub fn record(operation: FileOperation) {
+    let mut journal = JOURNAL.lock().unwrap();
+
+    journal.redo.clear();
+    journal.undo.push_back(operation);
+    if journal.undo.len() > MAX_UNDO {
+        journal.undo.pop_front();
+    }
+}
+
+/// Forgets every operation on something under `root`, once that workspace
+/// is closed, so nothing outside the open workspace can be undone or redone.
+pub fn forget_root(root: &Path) {
+    let touches_root =
+        |operation: &FileOperation| operation.paths().iter().any(|path| Path::new(path).starts_with(root));
+    let mut journal = JOURNAL.lock().unwrap();
+
+    journal.undo.retain(|operation| !touches_root(operation));
+    journal.redo.retain(|(operation, _)| !touches_root(operation));
+}
+
+/// How many of the folders above `path` don't exist yet, i.e. how many
+/// `create_dir_all` is about to create for it.
+pub fn missing_parents(path: &Path) -> usize {
+    path.ancestors().skip(1).take_while(|dir| fs::symlink_metadata(dir).is_err()).count()
+}
+
+// Removes the folders `create` made above `path`, closest first, stopping at
+// the first one that is no longer empty
+fn remove_created_parents(path: &Path, created_parents: usize) {
+    for dir in path.ancestors().skip(1).take(created_parents) {
+        if workspace::check_entry(&dir.to_string_lossy()).is_err() || fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+fn exists(path: &str) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+fn no_longer_exists(path: &str) -> CommandError {
+    CommandError::not_found(format!("{} no longer exists", path), path)
+}
+
+fn already_exists(path: &str) -> CommandError {
+    CommandError::already_exists(format!("{} already exists", path), path)
+}
+
+// Reverses `operation`, but only if the file system still looks the way the
+// operation left it and every path is still inside an open workspace.
+// Returns the trash handle needed to redo it, if any.
+fn undo(operation: &FileOperation) -> Result<Option<TrashHandle>, CommandError> {
+    for path in operation.paths() {
+        workspace::check_entry(path)?;
+    }
+
+    match operation {
+        FileOperation::Create {
+            path,
+            is_directory,
+            created_parents,
+        } => {
+            let metadata = fs::symlink_metadata(path).map_err(|_| no_longer_exists(path))?;
+
+            // Only throw away what is still exactly what was created
+            if *is_directory {
+                let is_empty = fs::read_dir(path).map(|mut d| d.next().is_none()).unwrap_or(false);
+                if !metadata.is_dir() || !is_empty {
+                    let message = format!("{} is no longer an empty folder", path);
+                    return Err(CommandError::new(ErrorKind::ModifiedOnDisk, message).with_path(path));
+                }
+                fs::remove_dir(path).map_err(|e| CommandError::io("remove folder", e).with_path(path))?;
+            } else {
+                if !metadata.is_file() || metadata.len() != 0 {
+                    let message = format!("{} has been modified since it was created", path);
+                    return Err(CommandError::new(ErrorKind::ModifiedOnDisk, message).with_path(path));
+                }
+                fs::remove_file(path).map_err(|e| CommandError::io("remove file", e).with_path(path))?;
+            }
+            remove_created_parents(Path::new(path), *created_parents);
+            Ok(None)
+        }
+        FileOperation::Rename { from, to } => {
+            if !exists(to) {
+                return Err(no_longer_exists(to));
+            }
+            if exists(from) {
+                return Err(already_exists(from));
+            }
+            move_path(Path::new(to), Path::new(from), &mut |_, _| {})?;
+            Ok(None)
+        }
+        FileOperation::Copy { path } => {
+            if !exists(path) {
+                return Err(no_longer_exists(path));
+            }
+            // The copy may have been edited since, so keep it recoverable
+            Ok(Some(move_to_trash(Path::new(path))?))
+        }
+        FileOperation::Delete { handle } => {
+            restore_trashed(handle)?;
+            Ok(None)
+        }
+    }
+}
+
+// Re-applies an undone operation, returning it as it should be recorded again.
+// Like `undo`, only inside an open workspace.
+fn redo(operation: FileOperation, handle: Option<TrashHandle>) -> Result<FileOperation, CommandError> {
+    for path in operation.paths() {
+        workspace::check_entry(path)?;
+    }
+
+    match operation {
+        FileOperation::Create { path, is_directory, .. } => {
+            if exists(&path) {
+                return Err(already_exists(&path));
+            }
+
+            let created_parents = missing_parents(Path::new(&path));
+            if let Some(parent) = Path::new(&path).parent().filter(|_| created_parents > 0) {
+                fs::create_dir_all(parent)
+                    .map_err(|e| CommandError::io("create parent directory", e).with_path(parent))?;
+            }
+            if is_directory {
+                fs::create_dir(&path).map_err(|e| CommandError::io("create directory", e).with_path(&path))?;
+            } else {
+                fs::write(&path, "").map_err(|e| CommandError::io("create file", e).with_path(&path))?;
+            }
+            Ok(FileOperation::Create {
+                path,
+                is_directory,
+                created_parents,
+            })
+        }
+        FileOperation::Rename { from, to } => {
+            if !exists(&from) {
+                return Err(no_longer_exists(&from));
+            }
+            if exists(&to) {
+                return Err(already_exists(&to));
+            }
+            move_path(Path::new(&from), Path::new(&to), &mut |_, _| {})?;
+            Ok(FileOperation::Rename { from, to })
+        }
+        FileOperation::Copy { path } => {
+            let handle = handle.ok_or_else(|| CommandError::new(ErrorKind::NotFound, "Nothing to restore"))?;
+            restore_trashed(&handle)?;
+            Ok(FileOperation::Copy { path })
+        }
+        FileOperation::Delete { handle } => {
+            if !exists(&handle.original_path) {
+                return Err(no_longer_exists(&handle.original_path));
+            }
+            let handle = move_to_trash(Path::new(&handle.original_path))?;
+            Ok(FileOperation::Delete { handle })
+        }
+    }
+}
+
+/// Undoes the most recent explorer operation and returns it. An operation
+/// whose files have changed since is dropped from the journal and reported
+/// as an error rather than forced.
+#[tauri::command]
+pub fn undo_file_operation() -> Result<Option<FileOperation>, CommandError> {
+    let mut journal = JOURNAL.lock().unwrap();
+
+    let Some(operation) = journal.undo.pop_back() else {
+        return Ok(None);
+    };
+
+    let handle = undo(&operation).map_err(|e| e.context("Cannot undo"))?;
+    journal.redo.push((operation.clone(), handle));
+
+    Ok(Some(operation))
+}
+
+/// Redoes the most recently undone operation and returns it.
+#[tauri::command]
+pub fn redo_file_operation() -> Result<Option<FileOperation>, CommandError> {
+    let mut journal = JOURNAL.lock().unwrap();
+
+    let Some((operation, handle)) = journal.redo.pop() else {
+        return Ok(None);
+    };
+
+    let operation = redo(operation, handle).map_err(|e| e.context("Cannot redo"))?;
+    journal.undo.push_back(operation.clone());
+
+    Ok(Some(operation))
+}
+
+/// Lists the undoable operations, most recent first.
+#[tauri::command]
+pub fn list_file_operations() -> Vec<FileOperation> {
+    let journal = JOURNAL.lock().unwrap();
+    journal.undo.iter().rev().cloned().collect()
+}
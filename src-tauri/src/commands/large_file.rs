@@ -0,0 +1,264 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use super::encoding::detect_encoding;
+use super::vfs::{self, ReadSeek};
+use crate::error::{CommandError, ErrorKind};
+
+// Global large file storage, keyed by file path
+lazy_static::lazy_static! {
+    static ref LARGE_FILES: Arc<Mutex<HashMap<String, Arc<LargeFile>>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+const SNIFF_LEN: usize = 64 * 1024;
+const PROGRESS_INTERVAL_MS: u64 = 100;
+
+// Upper bounds for a single read, so one request can't pull the file into memory
+const MAX_LINES_PER_READ: u64 = 10_000;
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+struct LargeFile {
+    // Local path or URI, as passed to `open_large_file`
+    path: String,
+    size: u64,
+    encoding: &'static Encoding,
+    // Shared by line reads; the index is built through its own handle
+    reader: Mutex<Box<dyn ReadSeek>>,
+    // Byte offset of the start of every line found so far
+    line_starts: RwLock<Vec<u64>>,
+    bytes_indexed: AtomicU64,
+    done: AtomicBool,
+    cancelled: AtomicBool,
+    // Why indexing stopped early, if it failed
+    error: Mutex<Option<CommandError>>,
+}
+
+#[derive(Serialize)]
+pub struct LargeFileInfo {
+    pub path: String,
+    pub size: u64,
+    pub encoding: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LargeFileProgress {
+    pub path: String,
+    pub bytes_indexed: u64,
+    pub line_count: u64,
+    pub done: bool,
+    pub error: Option<CommandError>, // Indexing failed; lines found so far stay readable
+}
+
+#[derive(Serialize)]
+pub struct LineRange {
+    pub start_line: u64,
+    pub lines: Vec<String>,
+    pub truncated_lines: Vec<u64>, // Lines cut off at MAX_LINE_BYTES
+    pub line_count: u64,           // Lines known so far; final once indexing is done
+    pub indexing: bool,
+    pub index_error: Option<CommandError>,
+}
+
+impl LargeFile {
+    // Complete lines available for reading. While indexing, the last line
+    // found may still extend further.
+    fn line_count(&self, line_starts: &[u64]) -> u64 {
+        let count = line_starts.len() as u64;
+        if self.done.load(Ordering::Relaxed) {
+            count
+        } else {
+            count.saturating_sub(1)
+        }
+    }
+
+    fn progress(&self) -> LargeFileProgress {
+        let line_starts = self.line_starts.read().unwrap();
+        LargeFileProgress {
+            path: self.path.clone(),
+            bytes_indexed: self.bytes_indexed.load(Ordering::Relaxed),
+            line_count: self.line_count(&line_starts),
+            done: self.done.load(Ordering::Relaxed),
+            error: self.error.lock().unwrap().clone(),
+        }
+    }
+
+    // Scans the file once for line breaks, publishing offsets after every chunk
+    fn build_index(&self, app: &AppHandle) -> Result<(), CommandError> {
+        let (mut file, _) = vfs::open_location(&self.path)?;
+        let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+        let mut offset = 0u64;
+        let mut last_progress = Instant::now();
+
+        while offset < self.size {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+
+            let read = file
+                .read(&mut buffer)
+                .map_err(|e| CommandError::io("read file", e).with_path(&self.path))?;
+            if read == 0 {
+                break;
+            }
+
+            // Only index what existed when the file was opened
+            let read = read.min((self.size - offset) as usize);
+            let starts: Vec<u64> = buffer[..read]
+                .iter()
+                .enumerate()
+                .filter(|(_, &b)| b == b'\n')
+                .map(|(i, _)| offset + i as u64 + 1)
+                .filter(|&start| start < self.size)
+                .collect();
+
+            self.line_starts.write().unwrap().extend(starts);
+            offset += read as u64;
+            self.bytes_indexed.store(offset, Ordering::Relaxed);
+
+            if last_progress.elapsed() >= Duration::from_millis(PROGRESS_INTERVAL_MS) {
+                let _ = app.emit("large-file-progress", self.progress());
+                last_progress = Instant::now();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_lines(&self, start_line: u64, end_line: u64) -> Result<LineRange, CommandError> {
+        let (starts, line_count) = {
+            let line_starts = self.line_starts.read().unwrap();
+            let line_count = self.line_count(&line_starts);
+            let end_line = end_line.min(line_count).min(start_line.saturating_add(MAX_LINES_PER_READ));
+            let start_line = start_line.min(end_line);
+
+            // One extra offset (or the file size) marks where the last line ends
+            let mut starts: Vec<u64> = line_starts[start_line as usize..end_line as usize].to_vec();
+            starts.push(line_starts.get(end_line as usize).copied().unwrap_or(self.size));
+            (starts, line_count)
+        };
+
+        let mut file = self.reader.lock().unwrap();
+        let mut lines = Vec::with_capacity(starts.len() - 1);
+        let mut truncated_lines = Vec::new();
+
+        for (i, range) in starts.windows(2).enumerate() {
+            let length = range[1] - range[0];
+            if length > MAX_LINE_BYTES {
+                truncated_lines.push(start_line + i as u64);
+            }
+
+            let mut bytes = vec![0u8; length.min(MAX_LINE_BYTES) as usize];
+            file.seek(SeekFrom::Start(range[0]))
+                .and_then(|_| file.read_exact(&mut bytes))
+                .map_err(|e| CommandError::io("read file", e).with_path(&self.path))?;
+
+            // Read-only view, so undecodable bytes are simply shown as U+FFFD
+            let (text, _) = self.encoding.decode_without_bom_handling(&bytes);
+            let text = text.strip_suffix('\n').unwrap_or(&text);
+            lines.push(text.strip_suffix('\r').unwrap_or(text).to_string());
+        }
+
+        Ok(LineRange {
+            start_line,
+            lines,
+            truncated_lines,
+            line_count,
+            indexing: !self.done.load(Ordering::Relaxed),
+            index_error: self.error.lock().unwrap().clone(),
+        })
+    }
+}
+
+fn not_open(path: &str) -> CommandError {
+    CommandError::new(ErrorKind::NotFound, format!("Large file not open: {}", path)).with_path(path)
+}
+
+fn get_large_file(path: &str) -> Result<Arc<LargeFile>, CommandError> {
+    LARGE_FILES.lock().unwrap().get(path).cloned().ok_or_else(|| not_open(path))
+}
+
+/// Opens `path` in read-only large file mode. `path` is a local path or a
+/// `vfs_*` URI, so remote files work too. The file is never loaded as a
+/// whole: a line index is built in the background (reported through
+/// `large-file-progress` events) and lines are fetched on demand with
+/// `read_large_file_lines`.
+#[tauri::command(async)]
+pub fn open_large_file(app: AppHandle, path: String) -> Result<LargeFileInfo, CommandError> {
+    let (mut file, size) = vfs::open_location(&path)?;
+
+    let mut sample = Vec::new();
+    file.by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut sample)
+        .map_err(|e| CommandError::io("read file", e).with_path(&path))?;
+
+    let (encoding, bom_len) = detect_encoding(&sample, sample.len() as u64 == size)
+        .ok_or_else(|| CommandError::new(ErrorKind::Binary, "Binary file cannot be displayed").with_path(&path))?;
+
+    // Line breaks are found by scanning for '\n' bytes, which UTF-16 doesn't allow
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let message = "Large file mode does not support UTF-16 files";
+        return Err(CommandError::new(ErrorKind::InvalidInput, message).with_path(&path));
+    }
+
+    let large_file = Arc::new(LargeFile {
+        path: path.clone(),
+        size,
+        encoding,
+        reader: Mutex::new(file),
+        line_starts: RwLock::new(if size > bom_len as u64 { vec![bom_len as u64] } else { Vec::new() }),
+        bytes_indexed: AtomicU64::new(0),
+        done: AtomicBool::new(false),
+        cancelled: AtomicBool::new(false),
+        error: Mutex::new(None),
+    });
+
+    // Reopening replaces (and stops) any earlier index of the same file
+    if let Some(previous) = LARGE_FILES.lock().unwrap().insert(path.clone(), large_file.clone()) {
+        previous.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    let index = large_file.clone();
+    thread::spawn(move || {
+        if let Err(e) = index.build_index(&app) {
+            *index.error.lock().unwrap() = Some(e);
+        }
+        if !index.cancelled.load(Ordering::Relaxed) {
+            index.done.store(true, Ordering::Relaxed);
+            let _ = app.emit("large-file-progress", index.progress());
+        }
+    });
+
+    Ok(LargeFileInfo {
+        path,
+        size,
+        encoding: encoding.name().to_string(),
+    })
+}
+
+/// Returns lines `start_line..end_line` (zero-based, end exclusive) of a file
+/// opened with `open_large_file`. While indexing is still running, only the
+/// lines found so far can be read.
+#[tauri::command(async)]
+pub fn read_large_file_lines(path: String, start_line: u64, end_line: u64) -> Result<LineRange, CommandError> {
+    get_large_file(&path)?.read_lines(start_line, end_line)
+}
+
+#[tauri::command]
+pub fn close_large_file(path: String) -> Result<(), CommandError> {
+    match LARGE_FILES.lock().unwrap().remove(&path) {
+        Some(large_file) => {
+            large_file.cancelled.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        None => Err(not_open(&path)),
+    }
+}
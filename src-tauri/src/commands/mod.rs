@@ -1,5 +1,23 @@
+pub mod app_data;
+pub mod archive;
+pub mod backup;
+pub mod binary;
+pub mod diff;
+pub mod editorconfig;
+pub mod encoding;
+pub mod file_index;
 pub mod filesystem;
 pub mod git;
+pub mod history;
+pub mod journal;
+pub mod large_file;
+pub mod preview;
 pub mod pty;
+pub mod search;
 pub mod ssh;
 pub mod ssh_pty;
+pub mod text_format;
+pub mod trash;
+pub mod vfs;
+pub mod watcher;
+pub mod workspace;
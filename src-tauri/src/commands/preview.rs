@@ -0,0 +1,273 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+// Data URLs are held in memory on both sides of the IPC bridge, so keep them modest
+pub const MAX_PREVIEW_SIZE: u64 = 20 * 1024 * 1024;
+
+lazy_static::lazy_static! {
+    static ref SVG_TAG: Regex = Regex::new(r"(?s)<svg\b[^>]*>").unwrap();
+    static ref SVG_ATTR: Regex = Regex::new(r#"\b(width|height|viewBox)\s*=\s*["']([^"']*)["']"#).unwrap();
+}
+
+#[derive(Serialize)]
+pub struct ImagePreview {
+    pub mime: String,
+    pub data_url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size: u64,
+}
+
+fn be_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32)
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)
+}
+
+fn le_u24(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 3).map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]))
+}
+
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    // IHDR is always the first chunk
+    let width = bytes.get(16..20)?;
+    let height = bytes.get(20..24)?;
+    Some((
+        u32::from_be_bytes(width.try_into().ok()?),
+        u32::from_be_bytes(height.try_into().ok()?),
+    ))
+}
+
+fn gif_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    Some((le_u16(bytes, 6)?, le_u16(bytes, 8)?))
+}
+
+// Walks the JPEG segments up to the first start-of-frame marker
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+
+    while i + 1 < bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        i += 2;
+
+        match marker {
+            // Fill bytes and markers without a length
+            0xFF => i -= 1,
+            0x01 | 0xD0..=0xD7 => {}
+            // SOF0-SOF15, except DHT, JPG and DAC which share the range
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                return Some((be_u16(bytes, i + 5)?, be_u16(bytes, i + 3)?));
+            }
+            _ => i += be_u16(bytes, i)? as usize,
+        }
+    }
+
+    None
+}
+
+fn webp_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        b"VP8 " => Some((le_u16(bytes, 26)? & 0x3FFF, le_u16(bytes, 28)? & 0x3FFF)),
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+            Some((1 + (bits & 0x3FFF), 1 + ((bits >> 14) & 0x3FFF)))
+        }
+        b"VP8X" => Some((1 + le_u24(bytes, 24)?, 1 + le_u24(bytes, 27)?)),
+        _ => None,
+    }
+}
+
+// Takes width/height from the root <svg> element, falling back to its viewBox
+fn svg_size(text: &str) -> Option<(u32, u32)> {
+    let tag = SVG_TAG.find(text)?.as_str();
+    let mut width = None;
+    let mut height = None;
+    let mut view_box = None;
+
+    for attr in SVG_ATTR.captures_iter(tag) {
+        let value = attr[2].trim();
+        // Only absolute sizes are useful here; "100%" and the like are skipped
+        let number = || value.strip_suffix("px").unwrap_or(value).parse::<f64>().ok();
+        match &attr[1] {
+            "width" => width = number(),
+            "height" => height = number(),
+            _ => view_box = Some(value.to_string()),
+        }
+    }
+
+    if let (Some(width), Some(height)) = (width, height) {
+        return Some((width.round() as u32, height.round() as u32));
+    }
+
+    let numbers: Vec<f64> = view_box?
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    match numbers[..] {
+        [_, _, width, height] => Some((width.round() as u32, height.round() as u32)),
+        _ => None,
+    }
+}
+
+fn is_svg(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(4096)];
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    // An XML prolog or comments may come first; HTML with inline SVG doesn't count
+    text.starts_with('<') && text.contains("<svg") && !text.contains("<html")
+}
+
+/// Recognizes PNG, JPEG, GIF and WebP by their magic bytes, and SVG by its
+/// root element. Returns the MIME type and dimensions, if they can be read.
+pub fn sniff_image(bytes: &[u8]) -> Option<(&'static str, Option<(u32, u32)>)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(("image/png", png_size(bytes)))
+    } else if bytes.starts_with(b"\xFF\xD8\xFF") {
+        Some(("image/jpeg", jpeg_size(bytes)))
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(("image/gif", gif_size(bytes)))
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some(("image/webp", webp_size(bytes)))
+    } else if is_svg(bytes) {
+        Some(("image/svg+xml", svg_size(&String::from_utf8_lossy(bytes))))
+    } else {
+        None
+    }
+}
+
+/// Builds a preview for image file content, or fails if it isn't an image.
+pub fn image_preview(bytes: &[u8]) -> Result<ImagePreview, CommandError> {
+    let (mime, dimensions) = sniff_image(bytes)
+        .ok_or_else(|| CommandError::new(ErrorKind::Binary, "File is not a supported image"))?;
+
+    Ok(ImagePreview {
+        mime: mime.to_string(),
+        data_url: format!("data:{};base64,{}", mime, BASE64.encode(bytes)),
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        size: bytes.len() as u64,
+    })
+}
+
+/// Returns an image file as a data URL along with its dimensions, so it can
+/// be previewed in a tab.
+#[tauri::command]
+pub fn read_image_preview(path: String) -> Result<ImagePreview, CommandError> {
+    let file_path = workspace::check_path(&path)?;
+
+    if !file_path.is_file() {
+        let message = format!("Path is not a file: {}", path);
+        return Err(CommandError::invalid_input(message).with_path(&path));
+    }
+
+    let metadata =
+        fs::metadata(&file_path).map_err(|e| CommandError::io("get file metadata", e).with_path(&path))?;
+    if metadata.len() > MAX_PREVIEW_SIZE {
+        let message = "Image is too large to preview (>20MB)";
+        return Err(CommandError::new(ErrorKind::TooLarge, message).with_path(&path));
+    }
+
+    let bytes = fs::read(&file_path).map_err(|e| CommandError::io("read file", e).with_path(&path))?;
+
+    image_preview(&bytes).map_err(|e| e.with_path(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SOI, an APP0 segment, then a baseline SOF0 for a 200x100 image
+    fn jpeg() -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        bytes.extend_from_slice(&[0; 14]);
+        bytes.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x64, 0x00, 0xC8, 0x03]);
+        bytes
+    }
+
+    fn webp(chunk: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = b"RIFF\0\0\0\0WEBP".to_vec();
+        bytes.extend_from_slice(chunk);
+        bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn jpeg_size_reads_the_frame_header() {
+        assert_eq!(jpeg_size(&jpeg()), Some((200, 100)));
+    }
+
+    #[test]
+    fn jpeg_size_skips_fill_bytes() {
+        let mut bytes = jpeg();
+        bytes.insert(20, 0xFF);
+        assert_eq!(jpeg_size(&bytes), Some((200, 100)));
+    }
+
+    #[test]
+    fn truncated_jpeg_has_no_size() {
+        let bytes = jpeg();
+        // Inside the APP0 segment, on the SOF marker, and inside the frame header
+        for len in [8, 21, bytes.len() - 2] {
+            assert_eq!(jpeg_size(&bytes[..len]), None, "truncated to {} bytes", len);
+        }
+        assert_eq!(sniff_image(&bytes[..8]), Some(("image/jpeg", None)));
+    }
+
+    #[test]
+    fn jpeg_without_markers_has_no_size() {
+        assert_eq!(jpeg_size(&[0xFF, 0xD8, 0x12, 0x34, 0x56]), None);
+    }
+
+    #[test]
+    fn webp_sizes() {
+        // Lossy: 14-bit dimensions after the frame tag and start code
+        let lossy = webp(b"VP8 ", &[0, 0, 0, 0x9D, 0x01, 0x2A, 0x40, 0x01, 0xF0, 0x00]);
+        assert_eq!(webp_size(&lossy), Some((320, 240)));
+
+        // Lossless: width - 1 and height - 1 packed into 14 bits each
+        let bits: u32 = (320 - 1) | ((240 - 1) << 14);
+        let mut payload = vec![0x2F];
+        payload.extend_from_slice(&bits.to_le_bytes());
+        assert_eq!(webp_size(&webp(b"VP8L", &payload)), Some((320, 240)));
+
+        // Extended: 24-bit width - 1 and height - 1 after the flags
+        let payload = [0, 0, 0, 0, 0x3F, 0x01, 0x00, 0xEF, 0x00, 0x00];
+        assert_eq!(webp_size(&webp(b"VP8X", &payload)), Some((320, 240)));
+    }
+
+    #[test]
+    fn truncated_webp_has_no_size() {
+        let lossy = webp(b"VP8 ", &[0, 0, 0, 0x9D, 0x01, 0x2A, 0x40, 0x01, 0xF0, 0x00]);
+        assert_eq!(webp_size(&lossy[..28]), None);
+        assert_eq!(webp_size(&lossy[..14]), None);
+
+        let extended = webp(b"VP8X", &[0, 0, 0, 0, 0x3F, 0x01, 0x00, 0xEF, 0x00, 0x00]);
+        assert_eq!(webp_size(&extended[..29]), None);
+        assert_eq!(sniff_image(&lossy[..12]), Some(("image/webp", None)));
+    }
+
+    #[test]
+    fn unknown_webp_chunk_has_no_size() {
+        assert_eq!(webp_size(&webp(b"ALPH", &[0; 16])), None);
+    }
+
+    #[test]
+    fn svg_size_from_attributes_or_view_box() {
+        assert_eq!(svg_size(r#"<svg width="24px" height="16">"#), Some((24, 16)));
+        assert_eq!(svg_size(r#"<svg width="100%" viewBox="0 0 48,32">"#), Some((48, 32)));
+        assert_eq!(svg_size("<svg>"), None);
+    }
+}
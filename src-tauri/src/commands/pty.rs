@@ -5,6 +5,8 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::{AppHandle, Emitter};
 
+use crate::error::{CommandError, ErrorKind};
+
 // Global PTY storage
 lazy_static::lazy_static! {
     static ref PTY_INSTANCES: Arc<Mutex<HashMap<String, PtyInstance>>> = Arc::new(Mutex::new(HashMap::new()));
@@ -17,8 +19,12 @@ struct PtyInstance {
     master: Box<dyn portable_pty::MasterPty + Send>,
 }
 
+fn pty_not_found(id: &str) -> CommandError {
+    CommandError::new(ErrorKind::SessionNotFound, format!("PTY instance not found: {}", id))
+}
+
 #[tauri::command]
-pub fn spawn_shell(app: AppHandle, id: String) -> Result<(), String> {
+pub fn spawn_shell(app: AppHandle, id: String) -> Result<(), CommandError> {
     use std::collections::hash_map::Entry;
 
     let pty_system = native_pty_system();
@@ -30,7 +36,7 @@ pub fn spawn_shell(app: AppHandle, id: String) -> Result<(), String> {
             pixel_width: 0,
             pixel_height: 0,
         })
-        .map_err(|e| format!("Failed to open PTY: {}", e))?;
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to open PTY: {}", e)))?;
 
     // Determine shell based on OS
     #[cfg(target_os = "windows")]
@@ -51,17 +57,17 @@ pub fn spawn_shell(app: AppHandle, id: String) -> Result<(), String> {
     let child = pair
         .slave
         .spawn_command(cmd)
-        .map_err(|e| format!("Failed to spawn shell: {}", e))?;
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to spawn shell: {}", e)))?;
 
     let writer = pair
         .master
         .take_writer()
-        .map_err(|e| format!("Failed to get writer: {}", e))?;
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to get writer: {}", e)))?;
 
     let mut reader = pair
         .master
         .try_clone_reader()
-        .map_err(|e| format!("Failed to get reader: {}", e))?;
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to get reader: {}", e)))?;
 
     // Store PTY instance using atomic check-and-insert to prevent TOCTOU race
     {
@@ -113,26 +119,26 @@ pub fn spawn_shell(app: AppHandle, id: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn write_to_pty(id: String, data: String) -> Result<(), String> {
+pub fn write_to_pty(id: String, data: String) -> Result<(), CommandError> {
     let mut instances = PTY_INSTANCES.lock().unwrap();
 
     if let Some(instance) = instances.get_mut(&id) {
         instance
             .writer
             .write_all(data.as_bytes())
-            .map_err(|e| format!("Failed to write to PTY: {}", e))?;
+            .map_err(|e| CommandError::io("write to PTY", e))?;
         instance
             .writer
             .flush()
-            .map_err(|e| format!("Failed to flush PTY: {}", e))?;
+            .map_err(|e| CommandError::io("flush PTY", e))?;
         Ok(())
     } else {
-        Err(format!("PTY instance not found: {}", id))
+        Err(pty_not_found(&id))
     }
 }
 
 #[tauri::command]
-pub fn resize_pty(id: String, rows: u16, cols: u16) -> Result<(), String> {
+pub fn resize_pty(id: String, rows: u16, cols: u16) -> Result<(), CommandError> {
     let instances = PTY_INSTANCES.lock().unwrap();
 
     if let Some(instance) = instances.get(&id) {
@@ -144,14 +150,14 @@ pub fn resize_pty(id: String, rows: u16, cols: u16) -> Result<(), String> {
                 pixel_width: 0,
                 pixel_height: 0,
             })
-            .map_err(|e| format!("Failed to resize PTY: {}", e))
+            .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to resize PTY: {}", e)))
     } else {
-        Err(format!("PTY instance not found: {}", id))
+        Err(pty_not_found(&id))
     }
 }
 
 #[tauri::command]
-pub fn kill_pty(id: String) -> Result<(), String> {
+pub fn kill_pty(id: String) -> Result<(), CommandError> {
     let mut instances = PTY_INSTANCES.lock().unwrap();
 
     if let Some(mut instance) = instances.remove(&id) {
@@ -159,6 +165,6 @@ pub fn kill_pty(id: String) -> Result<(), String> {
         let _ = instance.child.kill();
         Ok(())
     } else {
-        Err(format!("PTY instance not found: {}", id))
+        Err(pty_not_found(&id))
     }
 }
@@ -0,0 +1,597 @@
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use super::encoding::{self, DecodedText};
+use super::filesystem::{fingerprint, save_file, FileFingerprint};
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+// Cancellation flags for running searches, keyed by search ID
+lazy_static::lazy_static! {
+    static ref SEARCHES: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+// Matches are flushed to the frontend at least this often
+const BATCH_INTERVAL_MS: u64 = 100;
+
+// Same limit read_file_content applies; anything larger isn't source code
+const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+
+const DEFAULT_MAX_RESULTS: usize = 20_000;
+
+// Matched lines longer than this are cut down around the match
+const MAX_LINE_PREVIEW: usize = 250;
+
+#[derive(Deserialize)]
+pub struct SearchOptions {
+    pub query: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub context_lines: usize,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchMatch {
+    pub line_number: usize, // 1-based
+    pub column: usize,      // 1-based, in UTF-16 code units like Monaco
+    pub length: usize,      // in UTF-16 code units
+    pub line_text: String,
+    pub line_text_offset: usize, // UTF-16 units cut from the start of a long line
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchFileResult {
+    pub path: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchSummary {
+    pub files_searched: usize,
+    pub files_matched: usize,
+    pub match_count: usize,
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+/// Compiles the query into a regex honoring the literal/regex, case and
+/// whole-word options. Shared with project-wide replace.
+pub fn build_search_regex(options: &SearchOptions) -> Result<Regex, CommandError> {
+    if options.query.is_empty() {
+        return Err(CommandError::invalid_input("Search query is empty"));
+    }
+
+    let pattern = if options.is_regex {
+        options.query.clone()
+    } else {
+        regex::escape(&options.query)
+    };
+
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| CommandError::invalid_input(format!("Invalid search pattern: {}", e)))
+}
+
+/// Builds a walker over `root` that respects .gitignore/.ignore files, skips
+/// the .git directory and applies the include/exclude globs. Shared with
+/// project-wide replace.
+pub fn build_search_walker(
+    root: &Path,
+    include: &[String],
+    exclude: &[String],
+) -> Result<ignore::Walk, CommandError> {
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in include {
+        overrides
+            .add(glob)
+            .map_err(|e| CommandError::invalid_input(format!("Invalid include pattern '{}': {}", glob, e)))?;
+    }
+    for glob in exclude {
+        overrides
+            .add(&format!("!{}", glob))
+            .map_err(|e| CommandError::invalid_input(format!("Invalid exclude pattern '{}': {}", glob, e)))?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| CommandError::invalid_input(format!("Failed to build search patterns: {}", e)))?;
+
+    Ok(WalkBuilder::new(root)
+        .hidden(false)
+        .require_git(false)
+        .overrides(overrides)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build())
+}
+
+fn read_limited(path: &Path) -> Option<(fs::Metadata, Vec<u8>)> {
+    let metadata = fs::metadata(path).ok()?;
+    if metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+
+    let bytes = fs::read(path).ok()?;
+    Some((metadata, bytes))
+}
+
+/// Reads a file for searching, decoded from the encoding the editor would open
+/// it with. Returns `None` for anything that is too large, binary or not valid
+/// in its detected encoding.
+pub fn read_searchable_file(path: &Path) -> Option<DecodedText> {
+    let (_, bytes) = read_limited(path)?;
+    encoding::decode(&bytes).ok()
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+// Cuts very long lines (minified files) down to a window around the match.
+// Returns the preview and the byte offset it starts at.
+fn line_preview(line: &str, match_start: usize) -> (String, usize) {
+    if line.len() <= MAX_LINE_PREVIEW {
+        return (line.to_string(), 0);
+    }
+
+    let mut start = match_start.saturating_sub(MAX_LINE_PREVIEW / 4);
+    while !line.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = std::cmp::min(line.len(), start + MAX_LINE_PREVIEW);
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    (line[start..end].to_string(), start)
+}
+
+fn search_content(content: &str, regex: &Regex, context_lines: usize, limit: usize) -> Vec<SearchMatch> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut matches = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        for m in regex.find_iter(line) {
+            if matches.len() >= limit {
+                return matches;
+            }
+
+            // Zero-width matches (e.g. `^`) are not useful results
+            if m.start() == m.end() {
+                continue;
+            }
+
+            let (line_text, preview_start) = line_preview(line, m.start());
+            let context_before = lines[index.saturating_sub(context_lines)..index]
+                .iter()
+                .map(|l| l.to_string())
+                .collect();
+            let context_after = lines[index + 1..std::cmp::min(lines.len(), index + 1 + context_lines)]
+                .iter()
+                .map(|l| l.to_string())
+                .collect();
+
+            matches.push(SearchMatch {
+                line_number: index + 1,
+                column: utf16_len(&line[..m.start()]) + 1,
+                length: utf16_len(m.as_str()),
+                line_text,
+                line_text_offset: utf16_len(&line[..preview_start]),
+                context_before,
+                context_after,
+            });
+        }
+    }
+
+    matches
+}
+
+#[tauri::command]
+pub fn search_workspace(
+    app: AppHandle,
+    search_id: String,
+    root_path: String,
+    options: SearchOptions,
+) -> Result<(), CommandError> {
+    let root = workspace::check_path(&root_path)?;
+
+    if !root.is_dir() {
+        let message = format!("Path is not a directory: {}", root_path);
+        return Err(CommandError::invalid_input(message).with_path(&root_path));
+    }
+
+    // Validate everything up front so bad input is reported from the command itself
+    let regex = build_search_regex(&options)?;
+    let walker = build_search_walker(&root, &options.include, &options.exclude)?;
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut searches = SEARCHES.lock().unwrap();
+        if let Some(previous) = searches.insert(search_id.clone(), cancelled.clone()) {
+            // Reusing an ID supersedes the old search
+            previous.store(true, Ordering::Relaxed);
+        }
+    }
+
+    thread::spawn(move || {
+        let mut summary = SearchSummary {
+            files_searched: 0,
+            files_matched: 0,
+            match_count: 0,
+            truncated: false,
+            cancelled: false,
+        };
+        let mut batch: Vec<SearchFileResult> = Vec::new();
+        let mut last_flush = Instant::now();
+
+        for entry in walker.flatten() {
+            if cancelled.load(Ordering::Relaxed) {
+                summary.cancelled = true;
+                break;
+            }
+
+            if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+                continue;
+            }
+
+            let Some(content) = read_searchable_file(entry.path()) else {
+                continue;
+            };
+            summary.files_searched += 1;
+
+            let remaining = max_results - summary.match_count;
+            let matches = search_content(&content.text, &regex, options.context_lines, remaining);
+            if !matches.is_empty() {
+                summary.files_matched += 1;
+                summary.match_count += matches.len();
+                batch.push(SearchFileResult {
+                    path: entry.path().to_string_lossy().to_string(),
+                    matches,
+                });
+            }
+
+            if !batch.is_empty() && last_flush.elapsed() >= Duration::from_millis(BATCH_INTERVAL_MS) {
+                let _ = app.emit(&format!("search-results-{}", search_id), std::mem::take(&mut batch));
+                last_flush = Instant::now();
+            }
+
+            if summary.match_count >= max_results {
+                summary.truncated = true;
+                break;
+            }
+        }
+
+        if !batch.is_empty() && !summary.cancelled {
+            let _ = app.emit(&format!("search-results-{}", search_id), batch);
+        }
+        let _ = app.emit(&format!("search-done-{}", search_id), summary);
+
+        // Clean up, unless a newer search has taken over this ID
+        let mut searches = SEARCHES.lock().unwrap();
+        if searches.get(&search_id).map(|flag| Arc::ptr_eq(flag, &cancelled)).unwrap_or(false) {
+            searches.remove(&search_id);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_search(search_id: String) -> Result<(), CommandError> {
+    let searches = SEARCHES.lock().unwrap();
+
+    if let Some(cancelled) = searches.get(&search_id) {
+        cancelled.store(true, Ordering::Relaxed);
+        Ok(())
+    } else {
+        Err(CommandError::new(ErrorKind::NotFound, format!("Search not found: {}", search_id)))
+    }
+}
+
+// ============================================================================
+// Project-wide replace
+// ============================================================================
+
+#[derive(Serialize)]
+pub struct ReplaceMatch {
+    pub index: usize, // Position of the match in the file, used to select it for apply
+    pub line_number: usize,
+    pub column: usize,
+    pub length: usize,
+    pub line_text: String,
+    pub replacement_text: String,
+}
+
+#[derive(Serialize)]
+pub struct ReplaceFilePreview {
+    pub path: String,
+    pub fingerprint: FileFingerprint,
+    pub matches: Vec<ReplaceMatch>,
+}
+
+#[derive(Serialize)]
+pub struct ReplacePreview {
+    pub files: Vec<ReplaceFilePreview>,
+    pub truncated: bool, // Stopped at max_results; more files may match
+}
+
+#[derive(Deserialize)]
+pub struct ReplaceSelection {
+    pub path: String,
+    pub fingerprint: FileFingerprint,
+    pub match_indices: Vec<usize>,
+}
+
+#[derive(Serialize)]
+pub struct ReplaceResult {
+    pub files_changed: usize,
+    pub replacements: usize,
+    /// Files skipped because they changed on disk after the preview
+    pub conflicts: Vec<String>,
+}
+
+// Runs the replacement over every line of `content`. Returns a preview of each
+// match and the new content with only the `selected` matches replaced (all of
+// them when `selected` is `None`). Literal searches insert the replacement
+// verbatim; regex searches expand `$1`/`${name}` capture references.
+fn replace_in_content(
+    content: &str,
+    regex: &Regex,
+    replacement: &str,
+    expand: bool,
+    selected: Option<&HashSet<usize>>,
+) -> (Vec<ReplaceMatch>, String) {
+    let mut matches = Vec::new();
+    let mut output = String::with_capacity(content.len());
+    let mut index = 0;
+
+    for (line_index, raw_line) in content.split_inclusive('\n').enumerate() {
+        // Keep the line terminator out of reach of the pattern
+        let line = raw_line.trim_end_matches('\n').trim_end_matches('\r');
+        let terminator = &raw_line[line.len()..];
+        let mut last_end = 0;
+
+        for caps in regex.captures_iter(line) {
+            let m = caps.get(0).unwrap();
+            if m.start() == m.end() {
+                continue;
+            }
+
+            let mut replacement_text = String::new();
+            if expand {
+                caps.expand(replacement, &mut replacement_text);
+            } else {
+                replacement_text.push_str(replacement);
+            }
+
+            if selected.map(|s| s.contains(&index)).unwrap_or(true) {
+                output.push_str(&line[last_end..m.start()]);
+                output.push_str(&replacement_text);
+                last_end = m.end();
+            }
+
+            let (line_text, _) = line_preview(line, m.start());
+            matches.push(ReplaceMatch {
+                index,
+                line_number: line_index + 1,
+                column: utf16_len(&line[..m.start()]) + 1,
+                length: utf16_len(m.as_str()),
+                line_text,
+                replacement_text,
+            });
+            index += 1;
+        }
+
+        output.push_str(&line[last_end..]);
+        output.push_str(terminator);
+    }
+
+    (matches, output)
+}
+
+// A file as read for replacing: its text, the bytes it was decoded from and
+// the fingerprint of those bytes
+struct ReplaceSource {
+    decoded: DecodedText,
+    bytes: Vec<u8>,
+    fingerprint: FileFingerprint,
+}
+
+fn read_for_replace(path: &Path) -> Option<ReplaceSource> {
+    let (metadata, bytes) = read_limited(path)?;
+    let decoded = encoding::decode(&bytes).ok()?;
+    Some(ReplaceSource {
+        fingerprint: fingerprint(&metadata, &bytes),
+        decoded,
+        bytes,
+    })
+}
+
+// A file `apply_replace` is about to rewrite
+struct PendingReplace {
+    path: PathBuf,
+    display_path: String, // As the frontend named it, for conflict reports
+    source: ReplaceSource,
+    new_bytes: Vec<u8>,
+    replacements: usize,
+}
+
+/// Computes every replacement `options` would make under `root_path` without
+/// touching any files. Each file carries the fingerprint it was previewed at
+/// so `apply_replace` can refuse files that changed in the meantime.
+#[tauri::command(async)]
+pub fn preview_replace(
+    root_path: String,
+    options: SearchOptions,
+    replacement: String,
+) -> Result<ReplacePreview, CommandError> {
+    let root = workspace::check_path(&root_path)?;
+
+    if !root.is_dir() {
+        let message = format!("Path is not a directory: {}", root_path);
+        return Err(CommandError::invalid_input(message).with_path(&root_path));
+    }
+
+    let regex = build_search_regex(&options)?;
+    let walker = build_search_walker(&root, &options.include, &options.exclude)?;
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    let mut preview = ReplacePreview {
+        files: Vec::new(),
+        truncated: false,
+    };
+    let mut match_count = 0;
+
+    for entry in walker.flatten() {
+        if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+            continue;
+        }
+
+        let Some(source) = read_for_replace(entry.path()) else {
+            continue;
+        };
+
+        let text = &source.decoded.text;
+        let (matches, _) = replace_in_content(text, &regex, &replacement, options.is_regex, None);
+        if matches.is_empty() {
+            continue;
+        }
+
+        match_count += matches.len();
+        preview.files.push(ReplaceFilePreview {
+            path: entry.path().to_string_lossy().to_string(),
+            fingerprint: source.fingerprint,
+            matches,
+        });
+
+        if match_count >= max_results {
+            preview.truncated = true;
+            break;
+        }
+    }
+
+    Ok(preview)
+}
+
+/// Applies the selected replacements from a preview. Files whose content no
+/// longer matches the previewed fingerprint, checked again right before each
+/// write, are skipped and reported as conflicts. Files keep their encoding and
+/// BOM. Each file is written atomically, and if any write fails the files
+/// already written are restored so the workspace is never left half replaced;
+/// files that could not be restored are named in the error.
+#[tauri::command(async)]
+pub fn apply_replace(
+    options: SearchOptions,
+    replacement: String,
+    selections: Vec<ReplaceSelection>,
+) -> Result<ReplaceResult, CommandError> {
+    let regex = build_search_regex(&options)?;
+
+    let mut result = ReplaceResult {
+        files_changed: 0,
+        replacements: 0,
+        conflicts: Vec::new(),
+    };
+    let mut pending: Vec<PendingReplace> = Vec::new();
+
+    // Work out every new file content before writing anything
+    for selection in selections {
+        if selection.match_indices.is_empty() {
+            continue;
+        }
+
+        let path = workspace::check_path(&selection.path)?;
+        let unchanged =
+            read_for_replace(&path).filter(|source| source.fingerprint.same_content(&selection.fingerprint));
+
+        let Some(source) = unchanged else {
+            result.conflicts.push(selection.path);
+            continue;
+        };
+
+        let selected: HashSet<usize> = selection.match_indices.into_iter().collect();
+        let text = &source.decoded.text;
+        let (matches, new_content) =
+            replace_in_content(text, &regex, &replacement, options.is_regex, Some(&selected));
+
+        let applied = matches.iter().filter(|m| selected.contains(&m.index)).count();
+        if applied == 0 || &new_content == text {
+            continue;
+        }
+
+        // The replacement may bring in characters the file's encoding can't hold
+        let new_bytes = encoding::encode(&new_content, &source.decoded.encoding, source.decoded.has_bom)
+            .map_err(|e| {
+                let context = format!("Cannot replace in {}", selection.path);
+                e.context(&context).with_path(&selection.path)
+            })?;
+
+        pending.push(PendingReplace {
+            path,
+            display_path: selection.path,
+            source,
+            new_bytes,
+            replacements: applied,
+        });
+    }
+
+    let mut written: Vec<(&PendingReplace, FileFingerprint)> = Vec::new();
+    for file in &pending {
+        match save_file(&file.path, &file.new_bytes, Some(&file.source.fingerprint)) {
+            Ok(fingerprint) => written.push((file, fingerprint)),
+            // Changed since it was read above
+            Err(e) if e.kind == ErrorKind::ModifiedOnDisk => result.conflicts.push(file.display_path.clone()),
+            Err(e) => {
+                // Roll back what was already replaced, unless it has been changed again since
+                let not_restored: Vec<String> = written
+                    .into_iter()
+                    .filter(|(written_file, fingerprint)| {
+                        save_file(&written_file.path, &written_file.source.bytes, Some(fingerprint)).is_err()
+                    })
+                    .map(|(written_file, _)| written_file.display_path.clone())
+                    .collect();
+
+                let mut error = e.context(&format!("Failed to replace in {}", file.path.display()));
+                if !not_restored.is_empty() {
+                    error.message.push_str(&format!(
+                        ". These files were replaced but could not be restored: {}",
+                        not_restored.join(", ")
+                    ));
+                }
+                return Err(error);
+            }
+        }
+    }
+
+    result.files_changed = written.len();
+    result.replacements = written.iter().map(|(file, _)| file.replacements).sum();
+    Ok(result)
+}
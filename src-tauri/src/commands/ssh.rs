@@ -1,13 +1,17 @@
 use serde::Deserialize;
-use ssh2::Session;
+use ssh2::{ErrorCode, FileStat, OpenFlags, OpenType, Session, Sftp};
 use std::collections::HashMap;
-use std::io::{Read, Write};
+use std::io::{Cursor, Read, Write};
 use std::net::TcpStream;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 // Reuse FileEntry from filesystem module
-use super::filesystem::FileEntry;
+use super::binary::{apply_patches, BytePatch};
+use super::filesystem::{compare_entries, content_hash, modified_on_disk, FileEntry, FileFingerprint};
+use super::trash::TrashHandle;
+use super::vfs::{FileData, FileProvider, ReadSeek, MAX_READ_SIZE};
+use crate::error::{CommandError, ErrorKind};
 
 // Global SSH session storage (public so ssh_pty can reuse sessions)
 lazy_static::lazy_static! {
@@ -32,6 +36,24 @@ fn expand_tilde(path: &str) -> String {
     path.to_string()
 }
 
+pub fn session_not_found(id: &str) -> CommandError {
+    CommandError::new(ErrorKind::SessionNotFound, format!("SSH session not found: {}", id))
+}
+
+// SFTP status codes carry the remote file error; any other libssh2 failure on
+// an established session means the connection itself has gone away
+pub fn ssh_error(action: &str, e: ssh2::Error) -> CommandError {
+    let kind = match e.code() {
+        ErrorCode::SFTP(2) => ErrorKind::NotFound,          // LIBSSH2_FX_NO_SUCH_FILE
+        ErrorCode::SFTP(3) => ErrorKind::PermissionDenied,  // LIBSSH2_FX_PERMISSION_DENIED
+        ErrorCode::SFTP(11) => ErrorKind::AlreadyExists,    // LIBSSH2_FX_FILE_ALREADY_EXISTS
+        ErrorCode::SFTP(12) => ErrorKind::ReadOnly,         // LIBSSH2_FX_WRITE_PROTECT
+        ErrorCode::SFTP(_) => ErrorKind::Io,
+        ErrorCode::Session(_) => ErrorKind::SessionLost,
+    };
+    CommandError::new(kind, format!("Failed to {}: {}", action, e))
+}
+
 #[derive(Deserialize)]
 pub struct SshAuthParams {
     pub auth_type: String, // "password" or "key"
@@ -40,6 +62,10 @@ pub struct SshAuthParams {
     pub key_passphrase: Option<String>,
 }
 
+fn auth_failed(method: &str, e: ssh2::Error) -> CommandError {
+    CommandError::new(ErrorKind::PermissionDenied, format!("{} authentication failed: {}", method, e))
+}
+
 #[tauri::command]
 pub fn ssh_connect(
     id: String,
@@ -47,47 +73,49 @@ pub fn ssh_connect(
     port: u16,
     user: String,
     auth: SshAuthParams,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     // Check if already connected
     {
         let sessions = SSH_SESSIONS.lock().unwrap();
         if sessions.contains_key(&id) {
-            return Err("Session already exists with this ID".to_string());
+            return Err(CommandError::new(ErrorKind::AlreadyExists, "Session already exists with this ID"));
         }
     }
 
     // Connect to SSH server
     let addr = format!("{}:{}", host, port);
     let tcp = TcpStream::connect(&addr)
-        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+        .map_err(|e| CommandError::io(&format!("connect to {}", addr), e))?;
 
     let mut session = Session::new()
-        .map_err(|e| format!("Failed to create SSH session: {}", e))?;
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to create SSH session: {}", e)))?;
 
     session.set_tcp_stream(tcp);
     session.handshake()
-        .map_err(|e| format!("SSH handshake failed: {}", e))?;
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("SSH handshake failed: {}", e)))?;
 
     // Authenticate
     match auth.auth_type.as_str() {
         "password" => {
-            let password = auth.password.ok_or("Password required for password auth")?;
+            let password = auth.password
+                .ok_or_else(|| CommandError::invalid_input("Password required for password auth"))?;
             session.userauth_password(&user, &password)
-                .map_err(|e| format!("Password authentication failed: {}", e))?;
+                .map_err(|e| auth_failed("Password", e))?;
         }
         "key" => {
-            let key_path = auth.key_path.ok_or("Key path required for key auth")?;
+            let key_path = auth.key_path
+                .ok_or_else(|| CommandError::invalid_input("Key path required for key auth"))?;
             let key_path = expand_tilde(&key_path);
             let key_path = Path::new(&key_path);
             let passphrase = auth.key_passphrase.as_deref();
             session.userauth_pubkey_file(&user, None, key_path, passphrase)
-                .map_err(|e| format!("Key authentication failed: {}", e))?;
+                .map_err(|e| auth_failed("Key", e))?;
         }
-        _ => return Err(format!("Unknown auth type: {}", auth.auth_type)),
+        _ => return Err(CommandError::invalid_input(format!("Unknown auth type: {}", auth.auth_type))),
     }
 
     if !session.authenticated() {
-        return Err("Authentication failed".to_string());
+        return Err(CommandError::new(ErrorKind::PermissionDenied, "Authentication failed"));
     }
 
     // Store session
@@ -102,13 +130,13 @@ pub fn ssh_connect(
 }
 
 #[tauri::command]
-pub fn ssh_disconnect(id: String) -> Result<(), String> {
+pub fn ssh_disconnect(id: String) -> Result<(), CommandError> {
     let mut sessions = SSH_SESSIONS.lock().unwrap();
 
     if sessions.remove(&id).is_some() {
         Ok(())
     } else {
-        Err(format!("SSH session not found: {}", id))
+        Err(session_not_found(&id))
     }
 }
 
@@ -118,177 +146,311 @@ pub fn ssh_is_connected(id: String) -> bool {
     sessions.contains_key(&id)
 }
 
-#[tauri::command]
-pub fn ssh_list_directory(id: String, path: String) -> Result<Vec<FileEntry>, String> {
-    let sessions = SSH_SESSIONS.lock().unwrap();
-    let ssh_session = sessions.get(&id)
-        .ok_or_else(|| format!("SSH session not found: {}", id))?;
-
-    let sftp = ssh_session.session.sftp()
-        .map_err(|e| format!("Failed to open SFTP channel: {}", e))?;
-
-    let entries = sftp.readdir(Path::new(&path))
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
-
-    let mut result: Vec<FileEntry> = entries
-        .into_iter()
-        .map(|(path_buf, stat)| {
-            let name = path_buf.file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
-            let full_path = path_buf.to_string_lossy().to_string();
-            let is_directory = stat.is_dir();
-            let is_symlink = stat.file_type().is_symlink();
-            let size = stat.size.unwrap_or(0);
-
-            FileEntry {
-                name,
-                path: full_path,
-                is_directory,
-                is_symlink,
-                size,
-            }
-        })
-        .collect();
-
-    // Sort: directories first, then alphabetically
-    result.sort_by(|a, b| {
-        match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+/// Files on the remote host of an open SSH session, over SFTP.
+pub struct SftpProvider {
+    session_id: String,
+}
+
+impl SftpProvider {
+    pub fn new(session_id: &str) -> Self {
+        SftpProvider {
+            session_id: session_id.to_string(),
         }
-    });
+    }
 
-    Ok(result)
-}
+    fn with_sftp<T>(&self, f: impl FnOnce(&Sftp) -> Result<T, CommandError>) -> Result<T, CommandError> {
+        let sessions = SSH_SESSIONS.lock().unwrap();
+        let ssh_session = sessions.get(&self.session_id)
+            .ok_or_else(|| session_not_found(&self.session_id))?;
 
-#[tauri::command]
-pub fn ssh_read_file(id: String, path: String) -> Result<String, String> {
-    let sessions = SSH_SESSIONS.lock().unwrap();
-    let ssh_session = sessions.get(&id)
-        .ok_or_else(|| format!("SSH session not found: {}", id))?;
+        let sftp = ssh_session.session.sftp()
+            .map_err(|e| ssh_error("open SFTP channel", e))?;
 
-    let sftp = ssh_session.session.sftp()
-        .map_err(|e| format!("Failed to open SFTP channel: {}", e))?;
+        f(&sftp)
+    }
+}
 
+fn read_all(sftp: &Sftp, path: &str) -> Result<FileData, CommandError> {
     // Get file stats first to check size
-    let stat = sftp.stat(Path::new(&path))
-        .map_err(|e| format!("Failed to stat file: {}", e))?;
+    let stat = sftp.stat(Path::new(path))
+        .map_err(|e| ssh_error("stat file", e).with_path(path))?;
 
-    let size = stat.size.unwrap_or(0);
-    if size > 50 * 1024 * 1024 {
-        return Err("File is too large (>50MB)".to_string());
+    if stat.size.unwrap_or(0) > MAX_READ_SIZE {
+        return Err(CommandError::new(ErrorKind::TooLarge, "File is too large (>50MB)").with_path(path));
     }
 
-    let mut file = sftp.open(Path::new(&path))
-        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut file = sftp.open(Path::new(path))
+        .map_err(|e| ssh_error("open file", e).with_path(path))?;
 
-    let mut contents = Vec::new();
-    file.read_to_end(&mut contents)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .map_err(|e| CommandError::io("read file", e).with_path(path))?;
 
-    // Check if binary
-    let check_bytes = &contents[..std::cmp::min(contents.len(), 8192)];
-    if check_bytes.contains(&0) {
-        return Err("Binary file cannot be displayed".to_string());
+    Ok(FileData {
+        fingerprint: remote_fingerprint(&stat, &bytes),
+        bytes,
+    })
+}
+
+// SFTP only reports mtimes in whole seconds
+fn remote_fingerprint(stat: &FileStat, bytes: &[u8]) -> FileFingerprint {
+    FileFingerprint {
+        mtime_ms: stat.mtime.unwrap_or(0) * 1000,
+        size: bytes.len() as u64,
+        hash: content_hash(bytes),
     }
+}
 
-    String::from_utf8(contents)
-        .map_err(|_| "File is not valid UTF-8 text".to_string())
+// The size from a stat settles most conflicts; only a file of the expected
+// size has to be read back and hashed
+fn check_unchanged(sftp: &Sftp, path: &str, expected: &FileFingerprint) -> Result<(), CommandError> {
+    let stat = match sftp.stat(Path::new(path)) {
+        Ok(stat) => stat,
+        Err(e) if e.code() == ErrorCode::SFTP(2) => return Err(modified_on_disk(path, None)),
+        Err(e) => return Err(ssh_error("stat file", e).with_path(path)),
+    };
+
+    if stat.size == Some(expected.size) {
+        let current = read_all(sftp, path)?;
+        if current.fingerprint.same_content(expected) {
+            return Ok(());
+        }
+        return Err(modified_on_disk(path, Some(current.fingerprint)));
+    }
+
+    let current = read_all(sftp, path).ok().map(|file| file.fingerprint);
+    Err(modified_on_disk(path, current))
 }
 
-#[tauri::command]
-pub fn ssh_write_file(id: String, path: String, content: String) -> Result<(), String> {
-    let sessions = SSH_SESSIONS.lock().unwrap();
-    let ssh_session = sessions.get(&id)
-        .ok_or_else(|| format!("SSH session not found: {}", id))?;
+impl FileProvider for SftpProvider {
+    fn list_directory(&self, path: &str) -> Result<Vec<FileEntry>, CommandError> {
+        self.with_sftp(|sftp| {
+            let entries = sftp.readdir(Path::new(path))
+                .map_err(|e| ssh_error("read directory", e).with_path(path))?;
+
+            let mut result: Vec<FileEntry> = entries
+                .into_iter()
+                .map(|(path_buf, stat)| FileEntry {
+                    name: path_buf.file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    path: path_buf.to_string_lossy().to_string(),
+                    is_directory: stat.is_dir(),
+                    is_symlink: stat.file_type().is_symlink(),
+                    size: stat.size.unwrap_or(0),
+                    is_ignored: false,
+                })
+                .collect();
+
+            result.sort_by(|a, b| compare_entries(a.is_directory, &a.name, b.is_directory, &b.name));
+
+            Ok(result)
+        })
+    }
 
-    let sftp = ssh_session.session.sftp()
-        .map_err(|e| format!("Failed to open SFTP channel: {}", e))?;
+    fn read_file(&self, path: &str) -> Result<FileData, CommandError> {
+        self.with_sftp(|sftp| read_all(sftp, path))
+    }
 
-    let mut file = sftp.create(Path::new(&path))
-        .map_err(|e| format!("Failed to create/open file for writing: {}", e))?;
+    fn open_file(&self, path: &str) -> Result<(Box<dyn ReadSeek>, u64), CommandError> {
+        self.with_sftp(|sftp| {
+            let mut file = sftp.open(Path::new(path))
+                .map_err(|e| ssh_error("open file", e).with_path(path))?;
 
-    file.write_all(content.as_bytes())
-        .map_err(|e| format!("Failed to write file: {}", e))?;
+            let stat = file.stat()
+                .map_err(|e| ssh_error("stat file", e).with_path(path))?;
 
-    Ok(())
-}
+            if stat.is_dir() {
+                return Err(CommandError::invalid_input(format!("Path is not a file: {}", path)).with_path(path));
+            }
 
-#[tauri::command]
-pub fn ssh_delete(id: String, path: String) -> Result<(), String> {
-    let sessions = SSH_SESSIONS.lock().unwrap();
-    let ssh_session = sessions.get(&id)
-        .ok_or_else(|| format!("SSH session not found: {}", id))?;
+            Ok((Box::new(file) as Box<dyn ReadSeek>, stat.size.unwrap_or(0)))
+        })
+    }
 
-    let sftp = ssh_session.session.sftp()
-        .map_err(|e| format!("Failed to open SFTP channel: {}", e))?;
+    fn write_file(
+        &self,
+        path: &str,
+        bytes: &[u8],
+        expected: Option<&FileFingerprint>,
+    ) -> Result<FileFingerprint, CommandError> {
+        self.with_sftp(|sftp| {
+            if let Some(expected) = expected {
+                check_unchanged(sftp, path, expected)?;
+            }
 
-    // Check if it's a directory or file
-    let stat = sftp.stat(Path::new(&path))
-        .map_err(|e| format!("Failed to stat path: {}", e))?;
+            let mut file = sftp.create(Path::new(path))
+                .map_err(|e| ssh_error("create/open file for writing", e).with_path(path))?;
 
-    if stat.is_dir() {
-        sftp.rmdir(Path::new(&path))
-            .map_err(|e| format!("Failed to remove directory: {}", e))
-    } else {
-        sftp.unlink(Path::new(&path))
-            .map_err(|e| format!("Failed to delete file: {}", e))
+            file.write_all(bytes)
+                .map_err(|e| CommandError::io("write file", e).with_path(path))?;
+
+            let stat = file.stat()
+                .map_err(|e| ssh_error("stat file", e).with_path(path))?;
+
+            Ok(remote_fingerprint(&stat, bytes))
+        })
+    }
+
+    fn patch_file(
+        &self,
+        path: &str,
+        patches: &[BytePatch],
+        expected: Option<&FileFingerprint>,
+    ) -> Result<FileFingerprint, CommandError> {
+        self.with_sftp(|sftp| {
+            // The whole file is needed to fingerprint the result anyway, so
+            // it doubles as the check against `expected`
+            let mut current = read_all(sftp, path)?;
+            if let Some(expected) = expected {
+                if !current.fingerprint.same_content(expected) {
+                    return Err(modified_on_disk(path, Some(current.fingerprint)));
+                }
+            }
+
+            // Patching the copy first also validates every patch before the file is touched
+            let size = current.bytes.len() as u64;
+            apply_patches(&mut Cursor::new(&mut current.bytes), size, patches).map_err(|e| e.with_path(path))?;
+
+            // Plain WRITE (no TRUNCATE) so the rest of the file is left alone
+            let mut file = sftp.open_mode(Path::new(path), OpenFlags::WRITE, 0o644, OpenType::File)
+                .map_err(|e| ssh_error("open file for writing", e).with_path(path))?;
+            apply_patches(&mut file, size, patches).map_err(|e| e.with_path(path))?;
+
+            let stat = file.stat()
+                .map_err(|e| ssh_error("stat file", e).with_path(path))?;
+
+            Ok(remote_fingerprint(&stat, &current.bytes))
+        })
+    }
+
+    fn create_file(&self, path: &str) -> Result<(), CommandError> {
+        self.with_sftp(|sftp| {
+            // EXCLUSIVE makes the server refuse to replace an existing file
+            let flags = OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::EXCLUSIVE;
+            sftp.open_mode(Path::new(path), flags, 0o644, OpenType::File)
+                .map(|_| ())
+                .map_err(|e| match sftp.stat(Path::new(path)) {
+                    Ok(_) => CommandError::already_exists(format!("File already exists: {}", path), path),
+                    Err(_) => ssh_error("create file", e).with_path(path),
+                })
+        })
+    }
+
+    fn create_directory(&self, path: &str) -> Result<(), CommandError> {
+        self.with_sftp(|sftp| {
+            // Create with default permissions (0755)
+            sftp.mkdir(Path::new(path), 0o755)
+                .map_err(|e| ssh_error("create directory", e).with_path(path))
+        })
+    }
+
+    fn rename(&self, old_path: &str, new_path: &str) -> Result<(), CommandError> {
+        self.with_sftp(|sftp| {
+            sftp.rename(Path::new(old_path), Path::new(new_path), None)
+                .map_err(|e| ssh_error("rename", e).with_path(old_path))
+        })
+    }
+
+    fn delete(&self, path: &str) -> Result<Option<TrashHandle>, CommandError> {
+        self.with_sftp(|sftp| {
+            // lstat, so a symlink to a directory is removed rather than followed
+            let stat = sftp.lstat(Path::new(path))
+                .map_err(|e| ssh_error("stat path", e).with_path(path))?;
+
+            if stat.is_dir() {
+                remove_dir_all(sftp, Path::new(path))?;
+            } else {
+                sftp.unlink(Path::new(path))
+                    .map_err(|e| ssh_error("delete file", e).with_path(path))?;
+            }
+            Ok(None)
+        })
     }
 }
 
+// SFTP can only remove empty directories, so the contents go first. readdir
+// reports entries without following symlinks, so links are unlinked, not entered.
+fn remove_dir_all(sftp: &Sftp, path: &Path) -> Result<(), CommandError> {
+    let entries = sftp.readdir(path)
+        .map_err(|e| ssh_error("read directory", e).with_path(path))?;
+
+    for (child, stat) in entries {
+        if stat.is_dir() {
+            remove_dir_all(sftp, &child)?;
+        } else {
+            sftp.unlink(&child)
+                .map_err(|e| ssh_error("delete file", e).with_path(&child))?;
+        }
+    }
+
+    sftp.rmdir(path)
+        .map_err(|e| ssh_error("remove directory", e).with_path(path))
+}
+
 #[tauri::command]
-pub fn ssh_rename(id: String, old_path: String, new_path: String) -> Result<(), String> {
-    let sessions = SSH_SESSIONS.lock().unwrap();
-    let ssh_session = sessions.get(&id)
-        .ok_or_else(|| format!("SSH session not found: {}", id))?;
+pub fn ssh_list_directory(id: String, path: String) -> Result<Vec<FileEntry>, CommandError> {
+    SftpProvider::new(&id).list_directory(&path)
+}
+
+#[tauri::command]
+pub fn ssh_read_file(id: String, path: String) -> Result<String, CommandError> {
+    let contents = SftpProvider::new(&id).read_file(&path)?.bytes;
+
+    // Check if binary
+    let check_bytes = &contents[..std::cmp::min(contents.len(), 8192)];
+    if check_bytes.contains(&0) {
+        return Err(CommandError::new(ErrorKind::Binary, "Binary file cannot be displayed").with_path(&path));
+    }
 
-    let sftp = ssh_session.session.sftp()
-        .map_err(|e| format!("Failed to open SFTP channel: {}", e))?;
+    String::from_utf8(contents)
+        .map_err(|_| CommandError::new(ErrorKind::NotUtf8, "File is not valid UTF-8 text").with_path(&path))
+}
 
-    sftp.rename(Path::new(&old_path), Path::new(&new_path), None)
-        .map_err(|e| format!("Failed to rename: {}", e))
+#[tauri::command]
+pub fn ssh_write_file(id: String, path: String, content: String) -> Result<(), CommandError> {
+    SftpProvider::new(&id).write_file(&path, content.as_bytes(), None).map(|_| ())
 }
 
 #[tauri::command]
-pub fn ssh_create_dir(id: String, path: String) -> Result<(), String> {
-    let sessions = SSH_SESSIONS.lock().unwrap();
-    let ssh_session = sessions.get(&id)
-        .ok_or_else(|| format!("SSH session not found: {}", id))?;
+pub fn ssh_delete(id: String, path: String) -> Result<(), CommandError> {
+    SftpProvider::new(&id).delete(&path).map(|_| ())
+}
 
-    let sftp = ssh_session.session.sftp()
-        .map_err(|e| format!("Failed to open SFTP channel: {}", e))?;
+#[tauri::command]
+pub fn ssh_rename(id: String, old_path: String, new_path: String) -> Result<(), CommandError> {
+    SftpProvider::new(&id).rename(&old_path, &new_path)
+}
 
-    // Create with default permissions (0755)
-    sftp.mkdir(Path::new(&path), 0o755)
-        .map_err(|e| format!("Failed to create directory: {}", e))
+#[tauri::command]
+pub fn ssh_create_dir(id: String, path: String) -> Result<(), CommandError> {
+    SftpProvider::new(&id).create_directory(&path)
 }
 
 #[tauri::command]
-pub fn ssh_create_file(id: String, path: String) -> Result<(), String> {
-    ssh_write_file(id, path, String::new())
+pub fn ssh_create_file(id: String, path: String) -> Result<(), CommandError> {
+    SftpProvider::new(&id).create_file(&path)
 }
 
 #[tauri::command]
-pub fn ssh_get_home_dir(id: String) -> Result<String, String> {
+pub fn ssh_get_home_dir(id: String) -> Result<String, CommandError> {
     let sessions = SSH_SESSIONS.lock().unwrap();
     let ssh_session = sessions.get(&id)
-        .ok_or_else(|| format!("SSH session not found: {}", id))?;
+        .ok_or_else(|| session_not_found(&id))?;
 
     // Execute command to get home directory
     let mut channel = ssh_session.session.channel_session()
-        .map_err(|e| format!("Failed to open channel: {}", e))?;
+        .map_err(|e| ssh_error("open channel", e))?;
 
     channel.exec("echo $HOME")
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
+        .map_err(|e| ssh_error("execute command", e))?;
 
     let mut output = String::new();
     channel.read_to_string(&mut output)
-        .map_err(|e| format!("Failed to read output: {}", e))?;
+        .map_err(|e| CommandError::io("read output", e))?;
 
     channel.wait_close()
-        .map_err(|e| format!("Failed to close channel: {}", e))?;
+        .map_err(|e| ssh_error("close channel", e))?;
 
     Ok(output.trim().to_string())
 }
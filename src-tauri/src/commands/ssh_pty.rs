@@ -6,7 +6,8 @@ use std::thread;
 use tauri::{AppHandle, Emitter};
 
 // Import SSH_SESSIONS from ssh module to reuse existing connections
-use super::ssh::SSH_SESSIONS;
+use super::ssh::{session_not_found, ssh_error, SSH_SESSIONS};
+use crate::error::{CommandError, ErrorKind};
 
 // Global SSH PTY channel storage
 lazy_static::lazy_static! {
@@ -14,17 +15,21 @@ lazy_static::lazy_static! {
     static ref SSH_PTY_WRITERS: Arc<Mutex<HashMap<String, std::sync::mpsc::Sender<Vec<u8>>>>> = Arc::new(Mutex::new(HashMap::new()));
 }
 
+fn ssh_pty_not_found(pty_id: &str) -> CommandError {
+    CommandError::new(ErrorKind::SessionNotFound, format!("SSH PTY not found: {}", pty_id))
+}
+
 #[tauri::command]
 pub fn ssh_spawn_shell(
     app: AppHandle,
     session_id: String,
     pty_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     // Check if PTY already exists
     {
         let channels = SSH_PTY_CHANNELS.lock().unwrap();
         if channels.contains_key(&pty_id) {
-            return Err("SSH PTY already exists with this ID".to_string());
+            return Err(CommandError::new(ErrorKind::AlreadyExists, "SSH PTY already exists with this ID"));
         }
     }
 
@@ -32,22 +37,22 @@ pub fn ssh_spawn_shell(
     let channel = {
         let sessions = SSH_SESSIONS.lock().unwrap();
         let ssh_session = sessions.get(&session_id)
-            .ok_or_else(|| format!("SSH session not found: {}", session_id))?;
+            .ok_or_else(|| session_not_found(&session_id))?;
 
         // Set session to non-blocking for async reads
         ssh_session.session.set_blocking(false);
 
         // Open a new channel on the existing session
         let mut channel = ssh_session.session.channel_session()
-            .map_err(|e| format!("Failed to open channel: {}", e))?;
+            .map_err(|e| ssh_error("open channel", e))?;
 
         // Request PTY
         channel.request_pty("xterm-256color", None, Some((80, 24, 0, 0)))
-            .map_err(|e| format!("Failed to request PTY: {}", e))?;
+            .map_err(|e| ssh_error("request PTY", e))?;
 
         // Start shell
         channel.shell()
-            .map_err(|e| format!("Failed to start shell: {}", e))?;
+            .map_err(|e| ssh_error("start shell", e))?;
 
         channel
     };
@@ -149,31 +154,31 @@ pub fn ssh_spawn_shell(
 }
 
 #[tauri::command]
-pub fn ssh_write_to_shell(pty_id: String, data: String) -> Result<(), String> {
+pub fn ssh_write_to_shell(pty_id: String, data: String) -> Result<(), CommandError> {
     let writers = SSH_PTY_WRITERS.lock().unwrap();
 
     if let Some(tx) = writers.get(&pty_id) {
         tx.send(data.into_bytes())
-            .map_err(|e| format!("Failed to send data: {}", e))
+            .map_err(|e| CommandError::new(ErrorKind::SessionLost, format!("Failed to send data: {}", e)))
     } else {
-        Err(format!("SSH PTY not found: {}", pty_id))
+        Err(ssh_pty_not_found(&pty_id))
     }
 }
 
 #[tauri::command]
-pub fn ssh_resize_shell(pty_id: String, rows: u32, cols: u32) -> Result<(), String> {
+pub fn ssh_resize_shell(pty_id: String, rows: u32, cols: u32) -> Result<(), CommandError> {
     let mut channels = SSH_PTY_CHANNELS.lock().unwrap();
 
     if let Some(channel) = channels.get_mut(&pty_id) {
         channel.request_pty_size(cols, rows, None, None)
-            .map_err(|e| format!("Failed to resize: {}", e))
+            .map_err(|e| ssh_error("resize", e))
     } else {
-        Err(format!("SSH PTY not found: {}", pty_id))
+        Err(ssh_pty_not_found(&pty_id))
     }
 }
 
 #[tauri::command]
-pub fn ssh_kill_shell(pty_id: String) -> Result<(), String> {
+pub fn ssh_kill_shell(pty_id: String) -> Result<(), CommandError> {
     // Remove writer first to signal thread to stop
     {
         let mut writers = SSH_PTY_WRITERS.lock().unwrap();
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Mixed,
+}
+
+/// How text should be normalized when saved, from workspace settings or
+/// `.editorconfig`. Unset fields leave the content as it is.
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct SaveFormat {
+    /// `lf` or `crlf` converts every line; `mixed` keeps each line's own ending
+    pub line_ending: Option<LineEnding>,
+    pub insert_final_newline: Option<bool>,
+    /// Collapses blank lines at the end of the file into a single newline
+    pub trim_final_newlines: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+}
+
+/// Returns the line ending used by `text`, or `None` if it has no line breaks.
+pub fn detect_line_ending(text: &str) -> Option<LineEnding> {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+
+    match (lf, crlf) {
+        (0, 0) => None,
+        (_, 0) => Some(LineEnding::Lf),
+        (0, _) => Some(LineEnding::Crlf),
+        _ => Some(LineEnding::Mixed),
+    }
+}
+
+// Splits a line from `split_inclusive('\n')` into its content and terminator
+fn split_terminator(line: &str) -> (&str, &str) {
+    if let Some(body) = line.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = line.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+/// Applies `format` to `text`. Newlines that have to be added use the
+/// requested line ending, or else the first one found in the text.
+pub fn normalize(text: &str, format: &SaveFormat) -> String {
+    let target = match format.line_ending {
+        Some(LineEnding::Lf) => Some("\n"),
+        Some(LineEnding::Crlf) => Some("\r\n"),
+        _ => None,
+    };
+    let trim_whitespace = format.trim_trailing_whitespace.unwrap_or(false);
+
+    let mut normalized = String::with_capacity(text.len());
+    let mut first_ending = None;
+
+    for line in text.split_inclusive('\n') {
+        let (body, ending) = split_terminator(line);
+
+        if trim_whitespace {
+            normalized.push_str(body.trim_end_matches([' ', '\t']));
+        } else {
+            normalized.push_str(body);
+        }
+
+        if !ending.is_empty() {
+            let ending = target.unwrap_or(ending);
+            first_ending.get_or_insert(ending);
+            normalized.push_str(ending);
+        }
+    }
+
+    let eol = target.or(first_ending).unwrap_or("\n");
+
+    if format.trim_final_newlines.unwrap_or(false) {
+        let trimmed_len = normalized.trim_end_matches(['\r', '\n']).len();
+        if trimmed_len < normalized.len() {
+            normalized.truncate(trimmed_len);
+            normalized.push_str(eol);
+        }
+    }
+
+    if format.insert_final_newline.unwrap_or(false) && !normalized.is_empty() && !normalized.ends_with('\n') {
+        normalized.push_str(eol);
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_ending(line_ending: LineEnding) -> SaveFormat {
+        SaveFormat {
+            line_ending: Some(line_ending),
+            ..SaveFormat::default()
+        }
+    }
+
+    #[test]
+    fn detects_line_endings() {
+        assert_eq!(detect_line_ending("a\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(detect_line_ending("a\r\nb\r\n"), Some(LineEnding::Crlf));
+        assert_eq!(detect_line_ending("a\r\nb\n"), Some(LineEnding::Mixed));
+        assert_eq!(detect_line_ending("no break"), None);
+    }
+
+    #[test]
+    fn converts_crlf_to_lf() {
+        assert_eq!(normalize("a\r\nb\r\n", &with_ending(LineEnding::Lf)), "a\nb\n");
+    }
+
+    #[test]
+    fn converts_mixed_to_crlf() {
+        assert_eq!(normalize("a\nb\r\nc", &with_ending(LineEnding::Crlf)), "a\r\nb\r\nc");
+    }
+
+    #[test]
+    fn mixed_keeps_each_ending() {
+        assert_eq!(normalize("a\nb\r\nc\n", &with_ending(LineEnding::Mixed)), "a\nb\r\nc\n");
+    }
+
+    #[test]
+    fn lone_cr_is_not_a_line_break() {
+        assert_eq!(normalize("a\rb\n", &with_ending(LineEnding::Crlf)), "a\rb\r\n");
+        assert_eq!(detect_line_ending("a\rb"), None);
+    }
+
+    #[test]
+    fn added_newlines_follow_the_text() {
+        let format = SaveFormat {
+            insert_final_newline: Some(true),
+            ..SaveFormat::default()
+        };
+        assert_eq!(normalize("a\r\nb", &format), "a\r\nb\r\n");
+        assert_eq!(normalize("a", &format), "a\n");
+        assert_eq!(normalize("", &format), "");
+    }
+
+    #[test]
+    fn trims_whitespace_and_final_newlines() {
+        let format = SaveFormat {
+            trim_trailing_whitespace: Some(true),
+            trim_final_newlines: Some(true),
+            ..SaveFormat::default()
+        };
+        assert_eq!(normalize("a \t\r\nb  \r\n\r\n\r\n", &format), "a\r\nb\r\n");
+    }
+}
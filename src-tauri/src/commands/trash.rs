@@ -0,0 +1,382 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::CommandError;
+
+// Handles of items trashed by this app, keyed by handle ID. The webview only
+// ever names a handle by its ID, so it can't make up trash paths to restore.
+lazy_static::lazy_static! {
+    static ref TRASHED: Arc<Mutex<HashMap<String, TrashHandle>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+
+// Oldest handles are forgotten beyond this many
+const MAX_RESTORABLE: usize = 1000;
+
+/// Everything needed to put a trashed item back where it came from.
+#[derive(Serialize, Clone)]
+pub struct TrashHandle {
+    /// Pass to `restore_from_trash`
+    pub id: String,
+    pub original_path: String,
+    /// Where the item now lives inside the trash (Linux and macOS)
+    pub trashed_path: Option<String>,
+    /// The freedesktop `.trashinfo` file describing the item (Linux)
+    pub info_path: Option<String>,
+    pub deleted_at_ms: u64,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Absolute path to `path` without resolving the final component, so trashing
+// a symlink trashes the link rather than what it points at
+fn absolute_path(path: &Path) -> Result<PathBuf, CommandError> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| CommandError::invalid_input(format!("Cannot move to trash: {}", path.display())))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = fs::canonicalize(parent).map_err(|e| CommandError::io("resolve path", e).with_path(parent))?;
+    Ok(parent.join(file_name))
+}
+
+#[cfg(not(windows))]
+fn no_trash_dir() -> CommandError {
+    CommandError::new(crate::error::ErrorKind::NotFound, "Could not determine trash directory")
+}
+
+// ============================================================================
+// Freedesktop trash (Linux and other XDG desktops)
+// ============================================================================
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use super::{absolute_path, no_trash_dir, now_ms, TrashHandle};
+    use crate::error::{CommandError, ErrorKind};
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    // $XDG_DATA_HOME/Trash, i.e. ~/.local/share/Trash by default
+    fn home_trash() -> Option<PathBuf> {
+        dirs::data_dir().map(|d| d.join("Trash"))
+    }
+
+    // Walks up from `path` to the top directory of the filesystem it lives on
+    fn mount_root(path: &Path) -> PathBuf {
+        let dev = fs::symlink_metadata(path).map(|m| m.dev()).ok();
+        let mut root = path.to_path_buf();
+
+        while let Some(parent) = root.parent() {
+            if fs::metadata(parent).map(|m| m.dev()).ok() != dev {
+                break;
+            }
+            root = parent.to_path_buf();
+        }
+
+        root
+    }
+
+    // Picks the trash directory per the XDG Trash spec: the home trash for
+    // files on the same filesystem as it, otherwise a per-user trash at the
+    // top of the file's own filesystem so nothing has to be copied
+    fn trash_dir_for(path: &Path) -> Result<PathBuf, CommandError> {
+        let home_trash = home_trash().ok_or_else(no_trash_dir)?;
+
+        // The home trash may not exist yet, so compare against its closest existing ancestor
+        let home_dev = home_trash
+            .ancestors()
+            .find_map(|p| fs::metadata(p).ok())
+            .map(|m| m.dev());
+        let item_dev = fs::symlink_metadata(path).map(|m| m.dev()).ok();
+
+        if home_dev == item_dev {
+            return Ok(home_trash);
+        }
+
+        let topdir = mount_root(path);
+        let uid = unsafe { libc::getuid() };
+
+        // An admin-provided $topdir/.Trash must be a real, sticky directory
+        let shared = topdir.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared) {
+            if metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0 {
+                return Ok(shared.join(uid.to_string()));
+            }
+        }
+
+        Ok(topdir.join(format!(".Trash-{}", uid)))
+    }
+
+    // RFC 2396 escaping as required for the Path key of .trashinfo files
+    fn escape_path(path: &Path) -> String {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut escaped = String::new();
+        for &byte in path.as_os_str().as_bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                    escaped.push(byte as char)
+                }
+                _ => escaped.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        escaped
+    }
+
+    // "name", then "name.2.ext", "name.3.ext", ...
+    fn candidate_name(path: &Path, attempt: usize) -> String {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if attempt == 1 {
+            return name;
+        }
+
+        match (path.file_stem(), path.extension()) {
+            (Some(stem), Some(ext)) if !name.starts_with('.') => {
+                format!("{}.{}.{}", stem.to_string_lossy(), attempt, ext.to_string_lossy())
+            }
+            _ => format!("{}.{}", name, attempt),
+        }
+    }
+
+    pub fn move_to_trash(path: &Path) -> Result<TrashHandle, CommandError> {
+        let path = absolute_path(path)?;
+        let trash_dir = trash_dir_for(&path)?;
+        let files_dir = trash_dir.join("files");
+        let info_dir = trash_dir.join("info");
+
+        for dir in [&files_dir, &info_dir] {
+            fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(dir)
+                .map_err(|e| CommandError::io("create trash directory", e).with_path(dir))?;
+        }
+
+        let deletion_date = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S");
+
+        for attempt in 1..10_000 {
+            let name = candidate_name(&path, attempt);
+            let info_path = info_dir.join(format!("{}.trashinfo", name));
+            let trashed_path = files_dir.join(&name);
+
+            // Creating the info file exclusively is what reserves the name
+            let mut info_file = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(CommandError::io("write trash info", e).with_path(&info_path)),
+            };
+            if fs::symlink_metadata(&trashed_path).is_ok() {
+                let _ = fs::remove_file(&info_path);
+                continue;
+            }
+
+            let info = format!(
+                "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                escape_path(&path),
+                deletion_date
+            );
+            let written = info_file.write_all(info.as_bytes()).and_then(|_| info_file.sync_all());
+            if let Err(e) = written.and_then(|_| fs::rename(&path, &trashed_path)) {
+                let _ = fs::remove_file(&info_path);
+                return Err(CommandError::io("move to trash", e).with_path(&path));
+            }
+
+            return Ok(TrashHandle {
+                id: String::new(), // Assigned by move_to_trash
+                original_path: path.to_string_lossy().to_string(),
+                trashed_path: Some(trashed_path.to_string_lossy().to_string()),
+                info_path: Some(info_path.to_string_lossy().to_string()),
+                deleted_at_ms: now_ms(),
+            });
+        }
+
+        let message = format!("Failed to move to trash: no free name for {}", path.display());
+        Err(CommandError::new(ErrorKind::Io, message).with_path(&path))
+    }
+
+    pub fn restore(handle: &TrashHandle) -> Result<(), CommandError> {
+        super::restore_by_move(handle)
+    }
+}
+
+// ============================================================================
+// macOS: ~/.Trash
+// ============================================================================
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{absolute_path, now_ms, TrashHandle};
+    use crate::error::CommandError;
+    use std::fs;
+    use std::path::Path;
+
+    pub fn move_to_trash(path: &Path) -> Result<TrashHandle, CommandError> {
+        let path = absolute_path(path)?;
+        let trash_dir = dirs::home_dir()
+            .map(|h| h.join(".Trash"))
+            .ok_or_else(super::no_trash_dir)?;
+
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let (stem, ext) = match (path.file_stem(), path.extension()) {
+            (Some(stem), Some(ext)) => (stem.to_string_lossy().to_string(), format!(".{}", ext.to_string_lossy())),
+            _ => (name.clone(), String::new()),
+        };
+
+        // Finder-style "name 2.ext" when the name is already taken
+        let mut trashed_path = trash_dir.join(&name);
+        let mut attempt = 2;
+        while fs::symlink_metadata(&trashed_path).is_ok() {
+            trashed_path = trash_dir.join(format!("{} {}{}", stem, attempt, ext));
+            attempt += 1;
+        }
+
+        fs::rename(&path, &trashed_path).map_err(|e| CommandError::io("move to trash", e).with_path(&path))?;
+
+        Ok(TrashHandle {
+            id: String::new(), // Assigned by move_to_trash
+            original_path: path.to_string_lossy().to_string(),
+            trashed_path: Some(trashed_path.to_string_lossy().to_string()),
+            info_path: None,
+            deleted_at_ms: now_ms(),
+        })
+    }
+
+    pub fn restore(handle: &TrashHandle) -> Result<(), CommandError> {
+        super::restore_by_move(handle)
+    }
+}
+
+// ============================================================================
+// Windows: Recycle Bin
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::{absolute_path, now_ms, TrashHandle};
+    use crate::error::{CommandError, ErrorKind};
+    use std::path::{Path, PathBuf};
+
+    pub fn move_to_trash(path: &Path) -> Result<TrashHandle, CommandError> {
+        let path = absolute_path(path)?;
+        trash::delete(&path).map_err(|e| {
+            CommandError::new(ErrorKind::Io, format!("Failed to move to Recycle Bin: {}", e)).with_path(&path)
+        })?;
+
+        Ok(TrashHandle {
+            id: String::new(), // Assigned by move_to_trash
+            original_path: path.to_string_lossy().to_string(),
+            trashed_path: None,
+            info_path: None,
+            deleted_at_ms: now_ms(),
+        })
+    }
+
+    pub fn restore(handle: &TrashHandle) -> Result<(), CommandError> {
+        let original = PathBuf::from(&handle.original_path);
+        if original.exists() {
+            let message = format!("Destination already exists: {}", handle.original_path);
+            return Err(CommandError::already_exists(message, &original));
+        }
+
+        // The Recycle Bin has no stable ID to hand out up front, so pick the
+        // entry for this path deleted closest to when we trashed it
+        let deleted_at = (handle.deleted_at_ms / 1000) as i64;
+        let item = trash::os_limited::list()
+            .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to read Recycle Bin: {}", e)))?
+            .into_iter()
+            .filter(|item| item.original_path() == original)
+            .min_by_key(|item| (item.time_deleted - deleted_at).abs())
+            .ok_or_else(|| {
+                let message = format!("Item is no longer in the Recycle Bin: {}", handle.original_path);
+                CommandError::not_found(message, &original)
+            })?;
+
+        trash::os_limited::restore_all([item])
+            .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to restore from Recycle Bin: {}", e)))
+    }
+}
+
+#[cfg(not(windows))]
+fn restore_by_move(handle: &TrashHandle) -> Result<(), CommandError> {
+    let trashed_path = handle
+        .trashed_path
+        .as_ref()
+        .ok_or_else(|| CommandError::invalid_input("Trash handle has no trashed path"))?;
+    let original = Path::new(&handle.original_path);
+
+    if fs::symlink_metadata(trashed_path).is_err() {
+        let message = format!("Item is no longer in the trash: {}", handle.original_path);
+        return Err(CommandError::not_found(message, trashed_path));
+    }
+
+    if fs::symlink_metadata(original).is_ok() {
+        let message = format!("Destination already exists: {}", handle.original_path);
+        return Err(CommandError::already_exists(message, original));
+    }
+
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| CommandError::io("create parent directory", e).with_path(parent))?;
+    }
+
+    fs::rename(trashed_path, original)
+        .map_err(|e| CommandError::io("restore from trash", e).with_path(original))?;
+
+    if let Some(info_path) = &handle.info_path {
+        let _ = fs::remove_file(info_path);
+    }
+
+    Ok(())
+}
+
+/// Moves `path` to the platform trash and returns a handle for restoring it.
+pub fn move_to_trash(path: &Path) -> Result<TrashHandle, CommandError> {
+    let mut handle = platform::move_to_trash(path)?;
+    handle.id = format!("{}-{}", handle.deleted_at_ms, NEXT_HANDLE_ID.fetch_add(1, Ordering::Relaxed));
+
+    let mut trashed = TRASHED.lock().unwrap();
+    trashed.insert(handle.id.clone(), handle.clone());
+    if trashed.len() > MAX_RESTORABLE {
+        let oldest = trashed.values().min_by_key(|h| h.deleted_at_ms).map(|h| h.id.clone());
+        if let Some(oldest) = oldest {
+            trashed.remove(&oldest);
+        }
+    }
+
+    Ok(handle)
+}
+
+/// Puts a trashed item back at its original path. `handle` must come from
+/// `move_to_trash`, never from the webview.
+pub fn restore_trashed(handle: &TrashHandle) -> Result<(), CommandError> {
+    platform::restore(handle)?;
+    TRASHED.lock().unwrap().remove(&handle.id);
+    Ok(())
+}
+
+/// Restores an item deleted by this app, named by its handle's ID. The item
+/// must go back inside an open workspace.
+#[tauri::command]
+pub fn restore_from_trash(id: String) -> Result<(), CommandError> {
+    let handle = TRASHED.lock().unwrap().get(&id).cloned().ok_or_else(|| {
+        CommandError::new(crate::error::ErrorKind::NotFound, format!("Unknown trash handle: {}", id))
+    })?;
+
+    super::workspace::check_entry(&handle.original_path)?;
+    restore_trashed(&handle)
+}
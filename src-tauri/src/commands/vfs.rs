@@ -0,0 +1,341 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{Read, Seek};
+
+use super::binary::{self, read_range, BytePatch, ByteRange};
+use super::encoding;
+use super::filesystem::{self, fingerprint, save_file, FileEntry, FileFingerprint};
+use super::preview::{image_preview, ImagePreview, MAX_PREVIEW_SIZE};
+use super::ssh::SftpProvider;
+use super::text_format::{self, detect_line_ending, LineEnding, SaveFormat};
+use super::trash::TrashHandle;
+use super::workspace;
+use crate::error::{CommandError, ErrorKind};
+
+/// Largest file any provider reads whole, e.g. to open it as text.
+pub const MAX_READ_SIZE: u64 = 50 * 1024 * 1024;
+
+/// A whole file, with the fingerprint a later save is checked against.
+pub struct FileData {
+    pub bytes: Vec<u8>,
+    pub fingerprint: FileFingerprint,
+}
+
+/// An open file that can be read at any offset.
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// The file operations every backend supports. Paths are plain paths within
+/// the backend; the `vfs_*` commands address them with URIs instead.
+pub trait FileProvider {
+    /// Lists a directory, directories first, with entry paths as plain paths.
+    fn list_directory(&self, path: &str) -> Result<Vec<FileEntry>, CommandError>;
+    /// Reads a whole file, refusing files over `MAX_READ_SIZE`.
+    fn read_file(&self, path: &str) -> Result<FileData, CommandError>;
+    /// Opens a file for reading parts of it, and returns it with its size.
+    fn open_file(&self, path: &str) -> Result<(Box<dyn ReadSeek>, u64), CommandError>;
+    /// Creates or replaces a file and returns its new fingerprint. If
+    /// `expected` is given and the file no longer matches it, nothing is
+    /// written and a `ModifiedOnDisk` error is returned.
+    fn write_file(
+        &self,
+        path: &str,
+        bytes: &[u8],
+        expected: Option<&FileFingerprint>,
+    ) -> Result<FileFingerprint, CommandError>;
+    /// Overwrites byte ranges and returns the file's new fingerprint. Checked
+    /// against `expected` the same way as `write_file`.
+    fn patch_file(
+        &self,
+        path: &str,
+        patches: &[BytePatch],
+        expected: Option<&FileFingerprint>,
+    ) -> Result<FileFingerprint, CommandError>;
+    /// Creates an empty file, failing if something already exists there.
+    fn create_file(&self, path: &str) -> Result<(), CommandError>;
+    fn create_directory(&self, path: &str) -> Result<(), CommandError>;
+    fn rename(&self, old_path: &str, new_path: &str) -> Result<(), CommandError>;
+    /// Deletes a file, or a directory with everything in it. Returns the
+    /// handle to restore it with if it went to the trash.
+    fn delete(&self, path: &str) -> Result<Option<TrashHandle>, CommandError>;
+}
+
+/// Files on this machine, limited to the open workspace roots. Goes through
+/// the same code as the local filesystem commands, so saves keep local
+/// history and deletes go to the trash and can be undone.
+pub struct LocalProvider;
+
+impl FileProvider for LocalProvider {
+    fn list_directory(&self, path: &str) -> Result<Vec<FileEntry>, CommandError> {
+        filesystem::read_directory(path.to_string(), None)
+    }
+
+    fn read_file(&self, path: &str) -> Result<FileData, CommandError> {
+        let resolved = workspace::check_path(path)?;
+        let metadata =
+            fs::metadata(&resolved).map_err(|e| CommandError::io("get file metadata", e).with_path(path))?;
+
+        if !metadata.is_file() {
+            return Err(CommandError::invalid_input(format!("Path is not a file: {}", path)).with_path(path));
+        }
+        if metadata.len() > MAX_READ_SIZE {
+            return Err(CommandError::new(ErrorKind::TooLarge, "File is too large (>50MB)").with_path(path));
+        }
+
+        let bytes = fs::read(&resolved).map_err(|e| CommandError::io("read file", e).with_path(path))?;
+        Ok(FileData {
+            fingerprint: fingerprint(&metadata, &bytes),
+            bytes,
+        })
+    }
+
+    fn open_file(&self, path: &str) -> Result<(Box<dyn ReadSeek>, u64), CommandError> {
+        let resolved = workspace::check_path(path)?;
+        let file = File::open(&resolved).map_err(|e| CommandError::io("open file", e).with_path(path))?;
+        let metadata = file
+            .metadata()
+            .map_err(|e| CommandError::io("get file metadata", e).with_path(path))?;
+
+        if !metadata.is_file() {
+            return Err(CommandError::invalid_input(format!("Path is not a file: {}", path)).with_path(path));
+        }
+
+        Ok((Box::new(file), metadata.len()))
+    }
+
+    fn write_file(
+        &self,
+        path: &str,
+        bytes: &[u8],
+        expected: Option<&FileFingerprint>,
+    ) -> Result<FileFingerprint, CommandError> {
+        let resolved = workspace::check_path(path)?;
+        save_file(&resolved, bytes, expected)
+    }
+
+    fn patch_file(
+        &self,
+        path: &str,
+        patches: &[BytePatch],
+        expected: Option<&FileFingerprint>,
+    ) -> Result<FileFingerprint, CommandError> {
+        binary::patch_file_bytes(path.to_string(), patches.to_vec(), expected.cloned())
+    }
+
+    fn create_file(&self, path: &str) -> Result<(), CommandError> {
+        filesystem::create_file(path.to_string())
+    }
+
+    fn create_directory(&self, path: &str) -> Result<(), CommandError> {
+        filesystem::create_directory(path.to_string())
+    }
+
+    fn rename(&self, old_path: &str, new_path: &str) -> Result<(), CommandError> {
+        filesystem::rename_item(old_path.to_string(), new_path.to_string())
+    }
+
+    fn delete(&self, path: &str) -> Result<Option<TrashHandle>, CommandError> {
+        filesystem::delete_item(path.to_string()).map(Some)
+    }
+}
+
+/// Where a URI points: `file:///home/me/notes.txt` for local files, or
+/// `sftp://<session id>/home/me/notes.txt` for files on an open SSH session.
+/// Paths in URIs are kept as they are, not percent-encoded.
+#[derive(PartialEq)]
+enum Backend {
+    Local,
+    Sftp(String),
+}
+
+fn parse_uri(uri: &str) -> Result<(Backend, String), CommandError> {
+    if let Some(path) = uri.strip_prefix("file://") {
+        // file:///C:/dir on Windows names the path C:/dir
+        let path = match path.strip_prefix('/') {
+            Some(rest) if cfg!(windows) && rest.get(1..2) == Some(":") => rest,
+            _ => path,
+        };
+        return Ok((Backend::Local, path.to_string()));
+    }
+
+    if let Some(rest) = uri.strip_prefix("sftp://") {
+        if let Some(slash) = rest.find('/').filter(|&i| i > 0) {
+            return Ok((Backend::Sftp(rest[..slash].to_string()), rest[slash..].to_string()));
+        }
+    }
+
+    Err(CommandError::invalid_input(format!("Unsupported URI: {}", uri)))
+}
+
+fn to_uri(backend: &Backend, path: &str) -> String {
+    match backend {
+        Backend::Local if path.starts_with('/') => format!("file://{}", path),
+        Backend::Local => format!("file:///{}", path.replace('\\', "/")),
+        Backend::Sftp(session_id) => format!("sftp://{}{}", session_id, path),
+    }
+}
+
+fn provider(backend: &Backend) -> Box<dyn FileProvider> {
+    match backend {
+        Backend::Local => Box::new(LocalProvider),
+        Backend::Sftp(session_id) => Box::new(SftpProvider::new(session_id)),
+    }
+}
+
+fn resolve(uri: &str) -> Result<(Box<dyn FileProvider>, String), CommandError> {
+    let (backend, path) = parse_uri(uri)?;
+    Ok((provider(&backend), path))
+}
+
+/// Opens a file given by URI for reading parts of it, like
+/// `FileProvider::open_file`. A plain path is taken as a local file.
+pub fn open_location(location: &str) -> Result<(Box<dyn ReadSeek>, u64), CommandError> {
+    if location.contains("://") {
+        let (provider, path) = resolve(location)?;
+        provider.open_file(&path)
+    } else {
+        LocalProvider.open_file(location)
+    }
+}
+
+/// A text file read through a provider.
+#[derive(Serialize)]
+pub struct VfsTextFile {
+    pub content: String,
+    pub fingerprint: FileFingerprint, // Pass back to vfs_write_text_file
+    pub encoding: String,
+    pub has_bom: bool,
+    pub line_ending: Option<LineEnding>,
+}
+
+/// Lists a directory given by URI. Entry paths are URIs on the same backend.
+#[tauri::command(async)]
+pub fn vfs_read_directory(uri: String) -> Result<Vec<FileEntry>, CommandError> {
+    let (backend, path) = parse_uri(&uri)?;
+    let mut entries = provider(&backend).list_directory(&path)?;
+
+    for entry in &mut entries {
+        entry.path = to_uri(&backend, &entry.path);
+    }
+
+    Ok(entries)
+}
+
+/// Reads a file as text, detecting its encoding the same way on every backend.
+#[tauri::command(async)]
+pub fn vfs_read_text_file(uri: String) -> Result<VfsTextFile, CommandError> {
+    let (provider, path) = resolve(&uri)?;
+    let file = provider.read_file(&path)?;
+    let decoded = encoding::decode(&file.bytes).map_err(|e| e.with_path(&path))?;
+
+    Ok(VfsTextFile {
+        line_ending: detect_line_ending(&decoded.text),
+        content: decoded.text,
+        fingerprint: file.fingerprint,
+        encoding: decoded.encoding,
+        has_bom: decoded.has_bom,
+    })
+}
+
+/// Saves text to a file, with the same options as `write_file_content`, and
+/// returns the fingerprint of the saved file. Pass the fingerprint from the
+/// read as `expected_fingerprint` to refuse overwriting changes made since.
+#[tauri::command(async)]
+pub fn vfs_write_text_file(
+    uri: String,
+    content: String,
+    expected_fingerprint: Option<FileFingerprint>,
+    encoding: Option<String>,
+    with_bom: Option<bool>,
+    format: Option<SaveFormat>,
+) -> Result<FileFingerprint, CommandError> {
+    let (provider, path) = resolve(&uri)?;
+
+    let content = match format {
+        Some(format) => text_format::normalize(&content, &format),
+        None => content,
+    };
+    let bytes = encoding::encode(
+        &content,
+        encoding.as_deref().unwrap_or("utf-8"),
+        with_bom.unwrap_or(false),
+    )
+    .map_err(|e| e.with_path(&path))?;
+
+    provider.write_file(&path, &bytes, expected_fingerprint.as_ref())
+}
+
+/// Reads raw bytes for the hex viewer, on any backend.
+#[tauri::command(async)]
+pub fn vfs_read_file_bytes(uri: String, offset: u64, length: u64) -> Result<ByteRange, CommandError> {
+    let (provider, path) = resolve(&uri)?;
+    let (mut file, size) = provider.open_file(&path)?;
+    read_range(&mut file, size, offset, length).map_err(|e| e.with_path(&path))
+}
+
+/// Overwrites byte ranges of a file, as edited in the hex viewer, and returns
+/// its new fingerprint. Pass the fingerprint from the last read or patch as
+/// `expected_fingerprint` to refuse patching a file changed since.
+#[tauri::command(async)]
+pub fn vfs_patch_file_bytes(
+    uri: String,
+    patches: Vec<BytePatch>,
+    expected_fingerprint: Option<FileFingerprint>,
+) -> Result<FileFingerprint, CommandError> {
+    let (provider, path) = resolve(&uri)?;
+    provider.patch_file(&path, &patches, expected_fingerprint.as_ref())
+}
+
+/// Returns an image as a data URL with its dimensions, on any backend.
+#[tauri::command(async)]
+pub fn vfs_read_image_preview(uri: String) -> Result<ImagePreview, CommandError> {
+    let (provider, path) = resolve(&uri)?;
+    let (mut file, size) = provider.open_file(&path)?;
+
+    if size > MAX_PREVIEW_SIZE {
+        let message = "Image is too large to preview (>20MB)";
+        return Err(CommandError::new(ErrorKind::TooLarge, message).with_path(&path));
+    }
+
+    let mut bytes = Vec::with_capacity(size as usize);
+    file.read_to_end(&mut bytes)
+        .map_err(|e| CommandError::io("read file", e).with_path(&path))?;
+
+    image_preview(&bytes).map_err(|e| e.with_path(&path))
+}
+
+#[tauri::command(async)]
+pub fn vfs_create_file(uri: String) -> Result<(), CommandError> {
+    let (provider, path) = resolve(&uri)?;
+    provider.create_file(&path)
+}
+
+#[tauri::command(async)]
+pub fn vfs_create_directory(uri: String) -> Result<(), CommandError> {
+    let (provider, path) = resolve(&uri)?;
+    provider.create_directory(&path)
+}
+
+/// Renames or moves an entry within one backend.
+#[tauri::command(async)]
+pub fn vfs_rename(old_uri: String, new_uri: String) -> Result<(), CommandError> {
+    let (old_backend, old_path) = parse_uri(&old_uri)?;
+    let (new_backend, new_path) = parse_uri(&new_uri)?;
+
+    if old_backend != new_backend {
+        let message = format!("Cannot move {} to another filesystem: {}", old_uri, new_uri);
+        return Err(CommandError::invalid_input(message));
+    }
+
+    provider(&old_backend).rename(&old_path, &new_path)
+}
+
+/// Deletes an entry. Local entries go to the trash, like `delete_item`, and
+/// the returned handle's `id` can be passed to `restore_from_trash`. Remote
+/// ones are removed for good, directories with everything in them.
+#[tauri::command(async)]
+pub fn vfs_delete(uri: String) -> Result<Option<TrashHandle>, CommandError> {
+    let (provider, path) = resolve(&uri)?;
+    provider.delete(&path)
+}
@@ -0,0 +1,256 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+use notify_debouncer_full::notify::event::{EventKind, ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::filesystem::{ignore_chain, is_ignored};
+use crate::error::{CommandError, ErrorKind};
+
+// Global watcher storage, keyed by workspace root path
+lazy_static::lazy_static! {
+    static ref WATCHERS: Arc<Mutex<HashMap<String, Debouncer<RecommendedWatcher, RecommendedCache>>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+// Events are coalesced over this window before being sent to the frontend
+const DEBOUNCE_MS: u64 = 200;
+
+#[derive(Serialize, Clone)]
+pub struct FsChange {
+    pub kind: String, // "create", "modify", "delete" or "rename"
+    pub path: String,
+    pub old_path: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FsChangeBatch {
+    pub root: String,
+    pub changes: Vec<FsChange>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FsWatchError {
+    pub root: String,
+    pub message: String,
+}
+
+fn build_ignore_set(extra_patterns: &[String]) -> Result<GlobSet, CommandError> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in extra_patterns {
+        let mut globs = vec![pattern.clone()];
+
+        // Patterns are matched against paths relative to the root, so a bare
+        // name like "dist" should match that entry and its contents anywhere
+        if !pattern.contains('/') {
+            globs.push(format!("**/{}", pattern));
+            globs.push(format!("**/{}/**", pattern));
+        }
+
+        for glob in globs {
+            let glob = Glob::new(&glob).map_err(|e| {
+                CommandError::invalid_input(format!("Invalid ignore pattern '{}': {}", pattern, e))
+            })?;
+            builder.add(glob);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| CommandError::invalid_input(format!("Failed to build ignore patterns: {}", e)))
+}
+
+// What gets filtered out of a batch: git internals, anything .gitignore or
+// .ignore files exclude (the same rules as the explorer and the file index),
+// and the caller's own patterns
+struct EventFilter<'a> {
+    root: &'a Path,
+    ignore_set: &'a GlobSet,
+    // Ignore files that apply in each directory, loaded once per batch
+    chains: HashMap<PathBuf, Vec<Gitignore>>,
+}
+
+impl EventFilter<'_> {
+    fn is_ignored(&mut self, path: &Path) -> bool {
+        let relative = path.strip_prefix(self.root).unwrap_or(path);
+        if relative.components().any(|c| c.as_os_str() == ".git") || self.ignore_set.is_match(relative) {
+            return true;
+        }
+
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        let chain = self.chains.entry(parent.to_path_buf()).or_insert_with(|| ignore_chain(parent));
+        // Deleted paths can't be told apart; patterns for directories still
+        // match through the parents of the files below them
+        is_ignored(chain, path, path.is_dir())
+    }
+}
+
+fn to_changes(filter: &mut EventFilter, kind: &EventKind, paths: &[PathBuf]) -> Vec<FsChange> {
+    let path_string = |p: &Path| p.to_string_lossy().to_string();
+
+    match kind {
+        // A paired rename carries [from, to]; only drop it if both ends are ignored
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+            let (from, to) = (&paths[0], &paths[1]);
+            match (filter.is_ignored(from), filter.is_ignored(to)) {
+                (true, true) => Vec::new(),
+                (false, true) => vec![FsChange {
+                    kind: "delete".to_string(),
+                    path: path_string(from),
+                    old_path: None,
+                }],
+                (true, false) => vec![FsChange {
+                    kind: "create".to_string(),
+                    path: path_string(to),
+                    old_path: None,
+                }],
+                (false, false) => vec![FsChange {
+                    kind: "rename".to_string(),
+                    path: path_string(to),
+                    old_path: Some(path_string(from)),
+                }],
+            }
+        }
+        _ => {
+            let kind = match kind {
+                EventKind::Create(_) => "create",
+                EventKind::Remove(_) => "delete",
+                // Unpaired rename halves: the file either left or entered the watched tree
+                EventKind::Modify(ModifyKind::Name(RenameMode::From)) => "delete",
+                EventKind::Modify(ModifyKind::Name(RenameMode::To)) => "create",
+                EventKind::Modify(ModifyKind::Name(_)) => {
+                    if paths.first().map(|p| p.exists()).unwrap_or(false) { "create" } else { "delete" }
+                }
+                EventKind::Modify(ModifyKind::Metadata(_)) | EventKind::Access(_) => return Vec::new(),
+                EventKind::Modify(_) => "modify",
+                _ => return Vec::new(),
+            };
+
+            paths
+                .iter()
+                .filter(|p| !filter.is_ignored(p))
+                .map(|p| FsChange { kind: kind.to_string(), path: path_string(p), old_path: None })
+                .collect()
+        }
+    }
+}
+
+/// Watches `root_path` and emits debounced `fs-change` batches, skipping
+/// what git ignores and anything matching `ignore_patterns`. Failures while
+/// watching are emitted as `fs-watch-error`.
+#[tauri::command]
+pub fn start_watching(
+    app: AppHandle,
+    root_path: String,
+    ignore_patterns: Option<Vec<String>>,
+) -> Result<(), CommandError> {
+    use std::collections::hash_map::Entry;
+
+    let root = super::workspace::check_path(&root_path)?;
+
+    if !root.is_dir() {
+        let message = format!("Path is not a directory: {}", root_path);
+        return Err(CommandError::invalid_input(message).with_path(&root_path));
+    }
+
+    let ignore_set = build_ignore_set(&ignore_patterns.unwrap_or_default())?;
+
+    let mut watchers = WATCHERS.lock().unwrap();
+    let entry = match watchers.entry(root.to_string_lossy().to_string()) {
+        // Already watching (handles React StrictMode double-mount)
+        Entry::Occupied(_) => return Ok(()),
+        Entry::Vacant(entry) => entry,
+    };
+
+    let event_root = root.clone();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(DEBOUNCE_MS),
+        None,
+        move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let mut filter = EventFilter {
+                    root: &event_root,
+                    ignore_set: &ignore_set,
+                    chains: HashMap::new(),
+                };
+                let mut changes: Vec<FsChange> = Vec::new();
+                // Position of each path's change in `changes`, which keeps event order
+                let mut positions: HashMap<String, usize> = HashMap::new();
+
+                for event in events {
+                    for change in to_changes(&mut filter, &event.kind, &event.paths) {
+                        // Collapse repeated events for the same path within one batch,
+                        // but a create or rename followed by writes is still a create
+                        // or rename, so a rename keeps its old path
+                        match positions.get(&change.path) {
+                            Some(&i) => {
+                                let keeps_earlier = matches!(changes[i].kind.as_str(), "create" | "rename");
+                                if !(keeps_earlier && change.kind == "modify") {
+                                    changes[i] = change;
+                                }
+                            }
+                            None => {
+                                positions.insert(change.path.clone(), changes.len());
+                                changes.push(change);
+                            }
+                        }
+                    }
+                }
+
+                if !changes.is_empty() {
+                    super::file_index::apply_changes(&event_root, &changes);
+                    let root = event_root.to_string_lossy().to_string();
+                    let _ = app.emit("fs-change", FsChangeBatch { root, changes });
+                }
+            }
+            Err(errors) => {
+                let root = event_root.to_string_lossy().to_string();
+                for error in errors {
+                    let _ = app.emit("fs-watch-error", FsWatchError {
+                        root: root.clone(),
+                        message: error.to_string(),
+                    });
+                }
+            }
+        },
+    )
+    .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to create file watcher: {}", e)))?;
+
+    debouncer
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| {
+            CommandError::new(ErrorKind::Io, format!("Failed to watch directory: {}", e)).with_path(&root_path)
+        })?;
+
+    entry.insert(debouncer);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_watching(root_path: String) -> Result<(), CommandError> {
+    let mut watchers = WATCHERS.lock().unwrap();
+
+    if let Some(debouncer) = watchers.remove(&super::workspace::root_key(&root_path)) {
+        // Don't block the command on the debouncer thread winding down
+        debouncer.stop_nonblocking();
+        Ok(())
+    } else {
+        Err(CommandError::new(ErrorKind::NotFound, format!("Watcher not found: {}", root_path)))
+    }
+}
+
+/// Stops watching `root`, if it is watched, e.g. when its workspace is closed.
+pub fn stop_root(root: &Path) {
+    if let Some(debouncer) = WATCHERS.lock().unwrap().remove(&*root.to_string_lossy()) {
+        debouncer.stop_nonblocking();
+    }
+}
@@ -0,0 +1,245 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+use super::app_data::app_data_dir;
+use super::filesystem::atomic_write;
+use crate::error::{CommandError, ErrorKind};
+
+// Canonical paths the webview may touch: the open workspace roots. A root can
+// only be opened once the user picked it in the backend's folder dialog, so
+// the webview can't grant itself access to anything. Picked folders are
+// remembered across restarts (None until loaded from disk).
+lazy_static::lazy_static! {
+    static ref WORKSPACE_ROOTS: Arc<Mutex<Vec<PathBuf>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref PICKED_ROOTS: Arc<Mutex<Option<Vec<PathBuf>>>> = Arc::new(Mutex::new(None));
+}
+
+fn deny(path: &str, reason: &str) -> CommandError {
+    log::warn!("Denied filesystem access to {}: {}", path, reason);
+    let message = format!("Access denied: {} ({})", path, reason);
+    CommandError::new(ErrorKind::OutsideWorkspace, message).with_path(path)
+}
+
+// Canonicalizes the longest existing prefix of `path` and appends the rest,
+// so paths that are about to be created resolve too. The part that doesn't
+// exist yet may not contain `..`, which could otherwise climb back out.
+fn canonicalize_lenient(path: &Path) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err("path must be absolute".to_string());
+    }
+
+    let mut existing = path;
+    let mut missing = Vec::new();
+
+    loop {
+        match fs::canonicalize(existing) {
+            Ok(canonical) => {
+                let mut resolved = canonical;
+                for component in missing.iter().rev() {
+                    resolved.push(component);
+                }
+                return Ok(resolved);
+            }
+            Err(_) => {
+                let component = match existing.components().next_back() {
+                    Some(Component::Normal(name)) => name,
+                    _ => return Err("path cannot be resolved".to_string()),
+                };
+                missing.push(component);
+                existing = existing.parent().ok_or("path cannot be resolved")?;
+            }
+        }
+    }
+}
+
+fn is_permitted(resolved: &Path) -> bool {
+    WORKSPACE_ROOTS.lock().unwrap().iter().any(|root| resolved.starts_with(root))
+}
+
+/// The open workspace root holding `resolved`, a path returned by `check_path`.
+/// With nested roots, the innermost one.
+pub fn containing_root(resolved: &Path) -> Option<PathBuf> {
+    WORKSPACE_ROOTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|root| resolved.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .cloned()
+}
+
+/// Resolves `path` (following symlinks) and checks that it lies inside an
+/// open workspace root. Used by every command that reads or writes through
+/// a path from the webview, which should then only use the returned path, so
+/// a symlink changed after the check can't redirect the operation.
+pub fn check_path(path: &str) -> Result<PathBuf, CommandError> {
+    let resolved = canonicalize_lenient(Path::new(path)).map_err(|reason| deny(path, &reason))?;
+
+    if !is_permitted(&resolved) {
+        return Err(deny(path, "outside the open workspace"));
+    }
+
+    Ok(resolved)
+}
+
+/// Like `check_path`, but for commands that create, move or delete the entry
+/// itself. Only the parent directory is resolved, so a symlink is handled as
+/// the link rather than its target, and a workspace root can never be
+/// removed or renamed, as its parent is outside the workspace.
+pub fn check_entry(path: &str) -> Result<PathBuf, CommandError> {
+    let path_ref = Path::new(path);
+    let name = match path_ref.components().next_back() {
+        Some(Component::Normal(name)) => name,
+        _ => return Err(deny(path, "not a file or folder name")),
+    };
+    let parent = path_ref.parent().ok_or_else(|| deny(path, "no parent directory"))?;
+
+    let resolved = canonicalize_lenient(parent).map_err(|reason| deny(path, &reason))?.join(name);
+
+    // A root only covers what is below it
+    let permitted = WORKSPACE_ROOTS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|root| resolved.starts_with(root) && resolved != *root);
+
+    if !permitted {
+        return Err(deny(path, "outside the open workspace"));
+    }
+
+    Ok(resolved)
+}
+
+fn canonical_dir(path: &str) -> Result<PathBuf, CommandError> {
+    let canonical = fs::canonicalize(path).map_err(|e| CommandError::io("resolve path", e).with_path(path))?;
+
+    if !canonical.is_dir() {
+        return Err(CommandError::invalid_input(format!("Path is not a directory: {}", path)).with_path(path));
+    }
+
+    Ok(canonical)
+}
+
+fn picked_roots_file() -> Result<PathBuf, CommandError> {
+    Ok(app_data_dir()?.join("workspace_roots.json"))
+}
+
+// Runs `f` on the remembered roots, loading them on first use. A missing or
+// unreadable file just means nothing was picked yet.
+fn with_picked_roots<T>(f: impl FnOnce(&mut Vec<PathBuf>) -> T) -> T {
+    let mut picked = PICKED_ROOTS.lock().unwrap();
+    let roots = picked.get_or_insert_with(|| {
+        picked_roots_file()
+            .ok()
+            .and_then(|file| fs::read(file).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    });
+    f(roots)
+}
+
+fn remember_root(root: &Path) -> Result<(), CommandError> {
+    let roots = with_picked_roots(|roots| {
+        if !roots.iter().any(|r| r == root) {
+            roots.push(root.to_path_buf());
+        }
+        roots.clone()
+    });
+
+    let file = picked_roots_file()?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| CommandError::io("create data directory", e).with_path(dir))?;
+    }
+    let json = serde_json::to_vec(&roots)
+        .map_err(|e| CommandError::new(ErrorKind::Io, format!("Failed to serialize workspace roots: {}", e)))?;
+    atomic_write(&file, &json)
+}
+
+fn add_root(root: PathBuf) {
+    let mut roots = WORKSPACE_ROOTS.lock().unwrap();
+    if !roots.contains(&root) {
+        roots.push(root);
+    }
+}
+
+/// Asks the user for a folder with the system dialog and opens it as a
+/// workspace root. Returns the folder's canonical path, or None if the
+/// dialog was cancelled.
+#[tauri::command(async)]
+pub fn pick_workspace_root(app: AppHandle) -> Result<Option<String>, CommandError> {
+    let Some(picked) = app.dialog().file().set_title("Open Folder").blocking_pick_folder() else {
+        return Ok(None);
+    };
+    let path = picked
+        .into_path()
+        .map_err(|e| CommandError::invalid_input(format!("Unsupported folder: {}", e)))?;
+
+    let root = canonical_dir(&path.to_string_lossy())?;
+    remember_root(&root)?;
+    add_root(root.clone());
+
+    Ok(Some(root.to_string_lossy().to_string()))
+}
+
+/// Reopens a workspace root, e.g. the last folder on startup. Only folders
+/// the user picked with `pick_workspace_root` can be opened.
+#[tauri::command]
+pub fn open_workspace_root(path: String) -> Result<(), CommandError> {
+    let root = canonical_dir(&path)?;
+
+    if !with_picked_roots(|roots| roots.contains(&root)) {
+        return Err(deny(&path, "not a folder opened with the folder picker"));
+    }
+
+    add_root(root);
+    Ok(())
+}
+
+/// Closes a workspace root along with its file index, watcher and undo
+/// history, so nothing keeps serving a folder the user no longer has open.
+#[tauri::command]
+pub fn close_workspace_root(path: String) -> Result<(), CommandError> {
+    // A root that was deleted can't be canonicalized any more, so it is
+    // matched against the path it was recorded under instead
+    let root = match fs::canonicalize(&path) {
+        Ok(root) => root,
+        Err(e) => {
+            let given = Path::new(&path);
+            let resolved = canonicalize_lenient(given).ok();
+            WORKSPACE_ROOTS
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|root| root.as_path() == given || Some(*root) == resolved.as_ref())
+                .cloned()
+                .ok_or_else(|| CommandError::io("resolve path", e).with_path(&path))?
+        }
+    };
+    WORKSPACE_ROOTS.lock().unwrap().retain(|r| r != &root);
+    super::file_index::drop_root(&root);
+    super::watcher::stop_root(&root);
+    super::journal::forget_root(&root);
+    Ok(())
+}
+
+/// Key for per-root state such as file indexes and watchers. Every spelling of
+/// a root (trailing slash, symlinked parent, ...) maps to the same key; a root
+/// that no longer exists falls back to the path as given.
+pub fn root_key(path: &str) -> String {
+    fs::canonicalize(path)
+        .map(|root| root.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+#[tauri::command]
+pub fn list_workspace_roots() -> Vec<String> {
+    WORKSPACE_ROOTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|root| root.to_string_lossy().to_string())
+        .collect()
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::commands::filesystem::FileFingerprint;
+
+/// What went wrong, so the frontend can pick a reaction without matching on
+/// message text.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    AlreadyExists,
+    /// The operating system refused access
+    PermissionDenied,
+    /// The path is outside every open workspace root
+    OutsideWorkspace,
+    /// The file isn't writable; the UI can offer to save elsewhere or elevate
+    ReadOnly,
+    /// The file changed on disk since the caller read it
+    ModifiedOnDisk,
+    /// Text that isn't valid in its (detected) encoding
+    NotUtf8,
+    Binary,
+    TooLarge,
+    /// Text that can't be saved in the requested encoding
+    Unencodable,
+    InvalidInput,
+    /// No SSH session or terminal with the given ID
+    SessionNotFound,
+    /// The SSH connection dropped; reconnecting may help
+    SessionLost,
+    Git,
+    Io,
+}
+
+/// The error type returned by every Tauri command.
+#[derive(Serialize, Clone, Debug)]
+pub struct CommandError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub path: Option<String>,
+    pub os_code: Option<i32>,
+    /// Whether trying the same operation again might succeed
+    pub retryable: bool,
+    /// For `ModifiedOnDisk`: the file's current fingerprint, or `None` if it
+    /// has been deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<FileFingerprint>,
+}
+
+impl CommandError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        CommandError {
+            kind,
+            message: message.into(),
+            path: None,
+            os_code: None,
+            retryable: matches!(kind, ErrorKind::SessionLost),
+            current: None,
+        }
+    }
+
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_string_lossy().to_string());
+        self
+    }
+
+    /// Wraps an I/O error, keeping its kind and OS error code. The message
+    /// reads "Failed to {action}: {error}".
+    pub fn io(action: &str, error: io::Error) -> Self {
+        let kind = match error.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::ReadOnlyFilesystem => ErrorKind::ReadOnly,
+            io::ErrorKind::InvalidData => ErrorKind::NotUtf8,
+            _ => ErrorKind::Io,
+        };
+
+        let retryable = matches!(
+            error.kind(),
+            io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        );
+
+        CommandError {
+            os_code: error.raw_os_error(),
+            retryable,
+            ..CommandError::new(kind, format!("Failed to {}: {}", action, error))
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>, path: impl AsRef<Path>) -> Self {
+        CommandError::new(ErrorKind::NotFound, message).with_path(path)
+    }
+
+    pub fn already_exists(message: impl Into<String>, path: impl AsRef<Path>) -> Self {
+        CommandError::new(ErrorKind::AlreadyExists, message).with_path(path)
+    }
+
+    /// Prefixes the message, e.g. "Cannot undo: {message}".
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        CommandError::new(ErrorKind::InvalidInput, message)
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
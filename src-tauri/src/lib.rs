@@ -1,6 +1,7 @@
 mod commands;
+mod error;
 
-use tauri::{WebviewUrl, WebviewWindowBuilder};
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
 #[cfg(target_os = "macos")]
 use tauri::TitleBarStyle;
 
@@ -12,17 +13,59 @@ pub fn run() {
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
+        // Failures that don't fail a command, like a missed history snapshot,
+        // go to the log file and stdout
+        .plugin(tauri_plugin_log::Builder::new().level(log::LevelFilter::Info).build())
         .invoke_handler(tauri::generate_handler![
+            commands::workspace::pick_workspace_root,
+            commands::workspace::open_workspace_root,
+            commands::workspace::close_workspace_root,
+            commands::workspace::list_workspace_roots,
             commands::filesystem::read_directory,
+            commands::filesystem::read_directory_tree,
+            commands::archive::read_archive_directory,
+            commands::archive::read_archive_file,
             commands::filesystem::read_file_content,
+            commands::filesystem::read_text_file,
             commands::filesystem::write_file_content,
             commands::filesystem::create_file,
             commands::filesystem::create_directory,
             commands::filesystem::rename_item,
+            commands::filesystem::copy_item,
+            commands::filesystem::duplicate_item,
+            commands::filesystem::move_item,
             commands::filesystem::delete_item,
+            commands::filesystem::delete_item_permanently,
+            commands::trash::restore_from_trash,
+            commands::journal::undo_file_operation,
+            commands::journal::redo_file_operation,
+            commands::journal::list_file_operations,
             commands::filesystem::get_home_directory,
             commands::filesystem::file_exists,
             commands::filesystem::get_file_info,
+            commands::watcher::start_watching,
+            commands::watcher::stop_watching,
+            commands::search::search_workspace,
+            commands::search::cancel_search,
+            commands::search::preview_replace,
+            commands::search::apply_replace,
+            commands::file_index::build_file_index,
+            commands::file_index::query_file_index,
+            commands::file_index::drop_file_index,
+            commands::editorconfig::get_editorconfig,
+            commands::large_file::open_large_file,
+            commands::large_file::read_large_file_lines,
+            commands::large_file::close_large_file,
+            commands::binary::read_file_bytes,
+            commands::binary::patch_file_bytes,
+            commands::preview::read_image_preview,
+            commands::history::list_file_history,
+            commands::history::read_history_snapshot,
+            commands::history::restore_history_snapshot,
+            commands::backup::backup_buffer,
+            commands::backup::list_buffer_backups,
+            commands::backup::discard_buffer_backup,
+            commands::diff::compare_texts,
             commands::pty::spawn_shell,
             commands::pty::write_to_pty,
             commands::pty::resize_pty,
@@ -51,6 +94,17 @@ pub fn run() {
             commands::ssh::ssh_create_dir,
             commands::ssh::ssh_create_file,
             commands::ssh::ssh_get_home_dir,
+            // Provider-independent file commands, addressed by URI
+            commands::vfs::vfs_read_directory,
+            commands::vfs::vfs_read_text_file,
+            commands::vfs::vfs_write_text_file,
+            commands::vfs::vfs_create_file,
+            commands::vfs::vfs_create_directory,
+            commands::vfs::vfs_rename,
+            commands::vfs::vfs_delete,
+            commands::vfs::vfs_read_file_bytes,
+            commands::vfs::vfs_patch_file_bytes,
+            commands::vfs::vfs_read_image_preview,
             // SSH PTY commands
             commands::ssh_pty::ssh_spawn_shell,
             commands::ssh_pty::ssh_write_to_shell,
@@ -58,6 +112,10 @@ pub fn run() {
             commands::ssh_pty::ssh_kill_shell,
         ])
         .setup(|app| {
+            // Local history, backups and picked workspace roots live here
+            commands::app_data::set_app_data_dir(app.path().app_data_dir()?);
+            std::thread::spawn(commands::history::sweep);
+
             // Create main window programmatically
             let win_builder = WebviewWindowBuilder::new(app, "main", WebviewUrl::default())
                 .title("Lite")
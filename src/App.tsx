@@ -1,17 +1,29 @@
 import { useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { AppLayout } from './components/Layout/AppLayout';
+import { useHotExit } from './hooks/useHotExit';
 import { useKeybindings } from './hooks/useKeybindings';
 import { useEditorStore } from './stores/editorStore';
 
 function App() {
   useKeybindings();
+  useHotExit();
 
-  const { rootPath, toggleFolder } = useEditorStore();
+  const { rootPath, setRootPath, toggleFolder, restoreBufferBackups } = useEditorStore();
 
-  // Reload last opened folder on startup
+  // Reload last opened folder on startup, with any buffers left unsaved
   useEffect(() => {
     if (rootPath) {
-      toggleFolder(rootPath);
+      invoke('open_workspace_root', { path: rootPath })
+        .then(() => {
+          toggleFolder(rootPath);
+          return restoreBufferBackups();
+        })
+        .catch((error) => {
+          // e.g. the folder is gone, or wasn't opened with the folder picker
+          console.error('Failed to reopen workspace:', error);
+          setRootPath(null);
+        });
     }
   }, []);
 
@@ -1,5 +1,5 @@
 import { useRef, useCallback } from 'react';
-import MonacoEditor, { type OnMount, type BeforeMount } from '@monaco-editor/react';
+import MonacoEditor, { DiffEditor, type OnMount, type BeforeMount, type DiffOnMount } from '@monaco-editor/react';
 import { useEditorStore } from '../../stores/editorStore';
 
 export function Editor() {
@@ -70,6 +70,17 @@ export function Editor() {
     });
   }, []);
 
+  // While comparing with the file on disk, edits happen on the right-hand side
+  const handleDiffMount: DiffOnMount = useCallback((editor) => {
+    const modified = editor.getModifiedEditor();
+    modified.onDidChangeModelContent(() => {
+      const { activeTabId, updateContent } = useEditorStore.getState();
+      if (activeTabId) {
+        updateContent(activeTabId, modified.getValue());
+      }
+    });
+  }, []);
+
   const handleChange = useCallback((value: string | undefined) => {
     if (activeTabId && value !== undefined) {
       updateContent(activeTabId, value);
@@ -97,6 +108,32 @@ export function Editor() {
     );
   }
 
+  if (activeTab.diskContent !== undefined) {
+    return (
+      <DiffEditor
+        key={activeTab.id}
+        height="100%"
+        language={activeTab.language}
+        original={activeTab.diskContent}
+        modified={activeTab.content}
+        theme="lite-dark"
+        beforeMount={handleBeforeMount}
+        onMount={handleDiffMount}
+        options={{
+          originalEditable: false,
+          renderSideBySide: true,
+          minimap: { enabled: false },
+          fontSize,
+          tabSize,
+          fontFamily: 'Menlo, Monaco, Consolas, "Liberation Mono", "Courier New", monospace',
+          wordWrap: wordWrap ? 'on' : 'off',
+          automaticLayout: true,
+          scrollBeyondLastLine: false,
+        }}
+      />
+    );
+  }
+
   return (
     <MonacoEditor
       key={activeTab.id}
@@ -108,6 +145,7 @@ export function Editor() {
       onMount={handleMount}
       onChange={handleChange}
       options={{
+        readOnly: activeTab.readOnly,
         minimap: { enabled: false },
         fontSize,
         tabSize,
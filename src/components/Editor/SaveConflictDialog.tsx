@@ -0,0 +1,55 @@
+import { useEditorStore } from '../../stores/editorStore';
+
+// Asked when a save is refused because the file changed on disk since it
+// was opened, so the user's edits and the other change can't silently clobber
+// each other
+export function SaveConflictDialog() {
+  const { tabs, saveConflictTabId, resolveSaveConflict } = useEditorStore();
+
+  const tab = tabs.find(t => t.id === saveConflictTabId);
+  if (!tab) return null;
+
+  return (
+    <div className="fixed inset-0 bg-black/50 flex items-center justify-center z-50">
+      <div className="bg-editor-sidebar border border-editor-border rounded-lg shadow-xl w-[450px] overflow-hidden">
+        <div className="px-4 py-3 border-b border-editor-border">
+          <h2 className="text-sm font-medium text-editor-text">File Changed on Disk</h2>
+        </div>
+
+        <div className="p-4 space-y-4">
+          <p className="text-sm text-editor-text-muted">
+            "{tab.name}" was changed by another program since it was opened. Saving now would
+            overwrite those changes.
+          </p>
+
+          <div className="flex gap-2">
+            <button
+              onClick={() => resolveSaveConflict('cancel')}
+              className="flex-1 py-2 bg-editor-hover rounded text-sm text-editor-text-muted hover:text-editor-text"
+            >
+              Cancel
+            </button>
+            <button
+              onClick={() => resolveSaveConflict('compare')}
+              className="flex-1 py-2 bg-editor-hover rounded text-sm text-editor-text-muted hover:text-editor-text"
+            >
+              Compare
+            </button>
+            <button
+              onClick={() => resolveSaveConflict('reload')}
+              className="flex-1 py-2 bg-editor-hover rounded text-sm text-editor-text-muted hover:text-editor-text"
+            >
+              Reload
+            </button>
+            <button
+              onClick={() => resolveSaveConflict('overwrite')}
+              className="flex-1 py-2 bg-editor-accent hover:bg-editor-accent/80 rounded text-sm text-editor-text"
+            >
+              Overwrite
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
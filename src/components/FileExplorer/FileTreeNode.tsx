@@ -1,6 +1,7 @@
 import { useState } from 'react';
 import { useEditorStore } from '../../stores/editorStore';
 import { FileIcon } from './FileIcons';
+import { isArchive } from '../../lib/fileUtils';
 import type { FileEntry } from '../../types';
 
 interface FileTreeNodeProps {
@@ -34,7 +35,6 @@ export function FileTreeNode({
     remoteExpandedFolders,
     remoteFileTree,
     remoteToggleFolder,
-    remoteOpenFile,
   } = useEditorStore();
   const [renameValue, setRenameValue] = useState(entry.name);
 
@@ -46,22 +46,20 @@ export function FileTreeNode({
   const isExpanded = effectiveExpandedFolders.has(entry.path);
   const isSelected = selectedPath === entry.path;
   const isRenaming = renamingPath === entry.path;
-  const children = entry.is_directory ? effectiveFileTree.get(entry.path) || [] : [];
+  // Local archives expand like folders
+  const isFolder = entry.is_directory || (!isRemoteMode && isArchive(entry.path));
+  const children = isFolder ? effectiveFileTree.get(entry.path) || [] : [];
 
   const handleClick = () => {
     setSelectedPath(entry.path);
-    if (entry.is_directory) {
+    if (isFolder) {
       if (isRemoteMode) {
         remoteToggleFolder(entry.path);
       } else {
         toggleFolder(entry.path);
       }
     } else {
-      if (isRemoteMode) {
-        remoteOpenFile(entry.path);
-      } else {
-        openFile(entry.path);
-      }
+      openFile(entry.path, activeRemoteId ?? undefined);
     }
   };
 
@@ -88,7 +86,7 @@ export function FileTreeNode({
         onClick={handleClick}
         onContextMenu={(e) => onContextMenu(e, entry.path, entry.is_directory)}
       >
-        {entry.is_directory && (
+        {isFolder && (
           <span
             className={`mr-1 text-xs transition-transform duration-100 ${
               isExpanded ? 'rotate-90' : ''
@@ -116,11 +114,11 @@ export function FileTreeNode({
             onClick={(e) => e.stopPropagation()}
           />
         ) : (
-          <span className="truncate">{entry.name}</span>
+          <span className={`truncate ${entry.is_ignored ? 'opacity-50' : ''}`}>{entry.name}</span>
         )}
       </div>
 
-      {entry.is_directory && isExpanded && (
+      {isFolder && isExpanded && (
         <>
           {children.map((child) => (
             <FileTreeNode
@@ -5,6 +5,7 @@ import { FileExplorer } from '../FileExplorer/FileExplorer';
 import { GitPanel } from '../Git/GitPanel';
 import { EditorTabs } from '../Editor/EditorTabs';
 import { Editor } from '../Editor/Editor';
+import { SaveConflictDialog } from '../Editor/SaveConflictDialog';
 import { TerminalTabs } from '../Terminal/TerminalTabs';
 import { TerminalPanel } from '../Terminal/TerminalPanel';
 import { QuickOpen } from '../QuickOpen/QuickOpen';
@@ -86,6 +87,7 @@ export function AppLayout() {
       {quickOpenVisible && <QuickOpen />}
       {settingsVisible && <Settings />}
       {remoteDialogVisible && <RemoteDialog />}
+      <SaveConflictDialog />
 
       <StatusBar />
     </div>
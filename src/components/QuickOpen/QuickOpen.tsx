@@ -1,67 +1,57 @@
-import { useState, useEffect, useMemo, useRef, useCallback } from 'react';
+import { useState, useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useEditorStore } from '../../stores/editorStore';
-import { fuzzyMatch, fuzzyScore, getFileName } from '../../lib/fileUtils';
-import type { FileEntry } from '../../types';
+import { getFileName } from '../../lib/fileUtils';
+import type { FileMatch, FileQueryResult } from '../../types';
+
+const RESULT_LIMIT = 50;
 
 export function QuickOpen() {
   const { rootPath, toggleQuickOpen, openFile } = useEditorStore();
   const [query, setQuery] = useState('');
-  const [files, setFiles] = useState<string[]>([]);
+  const [filteredFiles, setFilteredFiles] = useState<FileMatch[]>([]);
   const [selectedIndex, setSelectedIndex] = useState(0);
   const [isLoading, setIsLoading] = useState(false);
+  const [indexVersion, setIndexVersion] = useState(0);
   const inputRef = useRef<HTMLInputElement>(null);
 
-  // Collect all files recursively
-  const collectFiles = useCallback(async (dirPath: string, collected: string[] = []): Promise<string[]> => {
-    try {
-      const entries = await invoke<FileEntry[]>('read_directory', { path: dirPath });
-
-      for (const entry of entries) {
-        // Skip hidden files and common excluded directories
-        if (entry.name.startsWith('.')) continue;
-        if (['node_modules', 'target', 'dist', 'build', '.git', '__pycache__', 'vendor'].includes(entry.name)) {
-          continue;
-        }
+  // Make sure the backend index exists and re-query once it finishes building
+  useEffect(() => {
+    if (!rootPath) return;
 
-        if (entry.is_directory) {
-          await collectFiles(entry.path, collected);
-        } else {
-          collected.push(entry.path);
-        }
+    const unlisten = listen<{ root: string }>('file-index-ready', (event) => {
+      if (event.payload.root === rootPath) {
+        setIndexVersion((v) => v + 1);
       }
-
-      return collected;
-    } catch {
-      return collected;
-    }
-  }, []);
-
-  // Load all files on open
-  useEffect(() => {
-    if (rootPath) {
-      setIsLoading(true);
-      collectFiles(rootPath).then((allFiles) => {
-        setFiles(allFiles);
-        setIsLoading(false);
-      });
-    }
+    });
+    invoke('build_file_index', { rootPath }).catch(console.error);
 
     // Focus input
     inputRef.current?.focus();
-  }, [rootPath, collectFiles]);
 
-  // Fuzzy filter and sort
-  const filteredFiles = useMemo(() => {
-    if (!query.trim()) {
-      return files.slice(0, 50);
-    }
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [rootPath]);
 
-    return files
-      .filter((f) => fuzzyMatch(f, query))
-      .sort((a, b) => fuzzyScore(b, query) - fuzzyScore(a, query))
-      .slice(0, 50);
-  }, [files, query]);
+  // Ranking happens in the backend; only the top matches come back
+  useEffect(() => {
+    if (!rootPath) return;
+
+    let cancelled = false;
+    invoke<FileQueryResult>('query_file_index', { rootPath, query, limit: RESULT_LIMIT })
+      .then((result) => {
+        if (cancelled) return;
+        setFilteredFiles(result.matches);
+        setIsLoading(result.indexing && result.matches.length === 0);
+      })
+      .catch(console.error);
+
+    return () => {
+      cancelled = true;
+    };
+  }, [rootPath, query, indexVersion]);
 
   // Reset selection when query changes
   useEffect(() => {
@@ -82,7 +72,7 @@ export function QuickOpen() {
       case 'Enter':
         e.preventDefault();
         if (filteredFiles[selectedIndex]) {
-          openFile(filteredFiles[selectedIndex]);
+          openFile(filteredFiles[selectedIndex].path);
           toggleQuickOpen();
         }
         break;
@@ -93,36 +83,18 @@ export function QuickOpen() {
     }
   };
 
-  const getRelativePath = (fullPath: string): string => {
-    if (rootPath && fullPath.startsWith(rootPath)) {
-      return fullPath.substring(rootPath.length + 1);
-    }
-    return fullPath;
-  };
-
-  // Highlight matching characters
-  const highlightMatch = (text: string, query: string): React.ReactNode => {
-    if (!query) return text;
-
-    const result: React.ReactNode[] = [];
-    let queryIndex = 0;
-    const textLower = text.toLowerCase();
-    const queryLower = query.toLowerCase();
-
-    for (let i = 0; i < text.length; i++) {
-      if (queryIndex < queryLower.length && textLower[i] === queryLower[queryIndex]) {
-        result.push(
-          <span key={i} className="text-editor-accent font-medium">
-            {text[i]}
-          </span>
-        );
-        queryIndex++;
-      } else {
-        result.push(text[i]);
-      }
-    }
-
-    return result;
+  // Highlight the characters the backend matched, given as code point
+  // indices into the relative path
+  const highlightMatch = (text: string, offset: number, positions: Set<number>): React.ReactNode => {
+    return Array.from(text).map((char, i) =>
+      positions.has(i + offset) ? (
+        <span key={i} className="text-editor-accent font-medium">
+          {char}
+        </span>
+      ) : (
+        char
+      )
+    );
   };
 
   return (
@@ -161,20 +133,21 @@ export function QuickOpen() {
             </div>
           ) : (
             filteredFiles.map((file, index) => {
-              const relativePath = getRelativePath(file);
-              const fileName = getFileName(file);
-              const dirPath = relativePath.replace(fileName, '');
+              const fileName = getFileName(file.relative_path);
+              const dirPath = file.relative_path.slice(0, file.relative_path.length - fileName.length);
+              const nameOffset = Array.from(dirPath).length;
+              const positions = new Set(file.positions);
 
               return (
                 <div
-                  key={file}
+                  key={file.path}
                   className={`px-4 py-2 cursor-pointer flex items-center ${
                     index === selectedIndex
                       ? 'bg-editor-accent text-white'
                       : 'hover:bg-editor-hover'
                   }`}
                   onClick={() => {
-                    openFile(file);
+                    openFile(file.path);
                     toggleQuickOpen();
                   }}
                   onMouseEnter={() => setSelectedIndex(index)}
@@ -182,7 +155,7 @@ export function QuickOpen() {
                   <span className="mr-2 text-sm opacity-60">📄</span>
                   <div className="flex-1 min-w-0">
                     <div className="text-sm truncate">
-                      {highlightMatch(fileName, query)}
+                      {highlightMatch(fileName, nameOffset, positions)}
                     </div>
                     {dirPath && (
                       <div
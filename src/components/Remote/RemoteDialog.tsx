@@ -1,6 +1,7 @@
 import { useState } from 'react';
 import { useEditorStore } from '../../stores/editorStore';
 import type { RemoteConnection } from '../../types';
+import { errorMessage } from '../../lib/errors';
 
 export function RemoteDialog() {
   const {
@@ -74,7 +75,7 @@ export function RemoteDialog() {
       setMode('list');
       setSelectedId(null);
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Connection failed');
+      setError(errorMessage(err, 'Connection failed'));
     } finally {
       setConnecting(false);
     }
@@ -116,7 +117,7 @@ export function RemoteDialog() {
       setMode('list');
       setSelectedId(null);
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Connection failed');
+      setError(errorMessage(err, 'Connection failed'));
       setMode('connect');
       setSelectedId(id);
     } finally {
@@ -1,5 +1,4 @@
 import { invoke } from '@tauri-apps/api/core';
-import { open } from '@tauri-apps/plugin-dialog';
 import { useEditorStore } from '../stores/editorStore';
 import type { FileEntry, FileInfo } from '../types';
 
@@ -8,13 +7,11 @@ export function useFileSystem() {
 
   const openFolder = async () => {
     try {
-      const selected = await open({
-        directory: true,
-        multiple: false,
-        title: 'Open Folder',
-      });
+      // The backend shows the dialog itself, as only folders picked there
+      // can be opened as a workspace
+      const selected = await invoke<string | null>('pick_workspace_root');
 
-      if (selected && typeof selected === 'string') {
+      if (selected) {
         setRootPath(selected);
       }
     } catch (error) {
@@ -0,0 +1,63 @@
+import { useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useEditorStore } from '../stores/editorStore';
+
+const BACKUP_INTERVAL_MS = 3000;
+
+// Periodically backs up dirty local buffers, so they can be restored if the
+// app closes before they are saved. Backups of tabs that were saved or
+// closed since are discarded.
+export function useHotExit() {
+  const rootPath = useEditorStore((state) => state.rootPath);
+  // Content last backed up, by path
+  const backedUp = useRef(new Map<string, string>());
+
+  useEffect(() => {
+    backedUp.current = new Map();
+    if (!rootPath) return;
+
+    let syncing = false;
+    const sync = async () => {
+      // A slow round must not overlap the next one
+      if (syncing) return;
+      syncing = true;
+
+      const dirty = useEditorStore
+        .getState()
+        .tabs.filter((t) => t.isDirty && !t.remote);
+      const dirtyPaths = new Set(dirty.map((t) => t.path));
+
+      for (const tab of dirty) {
+        if (backedUp.current.get(tab.path) === tab.content) continue;
+        try {
+          await invoke('backup_buffer', {
+            workspaceRoot: rootPath,
+            path: tab.path,
+            content: tab.content,
+            encoding: tab.encoding ?? null,
+            hasBom: tab.hasBom ?? null,
+            lineEnding: tab.lineEnding ?? null,
+          });
+          backedUp.current.set(tab.path, tab.content);
+        } catch (error) {
+          console.error('Failed to back up buffer:', error);
+        }
+      }
+
+      for (const path of [...backedUp.current.keys()]) {
+        if (dirtyPaths.has(path)) continue;
+        try {
+          await invoke('discard_buffer_backup', { workspaceRoot: rootPath, path });
+          backedUp.current.delete(path);
+        } catch (error) {
+          console.error('Failed to discard buffer backup:', error);
+        }
+      }
+
+      syncing = false;
+    };
+
+    const interval = setInterval(sync, BACKUP_INTERVAL_MS);
+    return () => clearInterval(interval);
+  }, [rootPath]);
+}
@@ -8,6 +8,7 @@ import { LigaturesAddon } from "@xterm/addon-ligatures";
 import { ImageAddon } from "@xterm/addon-image";
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { errorMessage } from "../lib/errors";
 import { getTheme } from "../lib/terminalThemes";
 import { useEditorStore } from "../stores/editorStore";
 
@@ -116,7 +117,7 @@ export function useTerminal({ id, onExit, remote }: UseTerminalOptions) {
           await invoke("spawn_shell", { id });
         }
       } catch (err) {
-        terminal.write(`\x1b[31mFailed to spawn shell: ${err}\x1b[0m\r\n`);
+        terminal.write(`\x1b[31mFailed to spawn shell: ${errorMessage(err)}\x1b[0m\r\n`);
       }
 
       // Initial resize
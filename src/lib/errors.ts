@@ -0,0 +1,16 @@
+import type { CommandError } from '../types';
+
+export function isCommandError(error: unknown): error is CommandError {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+}
+
+// Human-readable message for anything thrown by invoke() or elsewhere
+export function errorMessage(error: unknown, fallback = 'Unknown error'): string {
+  if (isCommandError(error) || error instanceof Error) {
+    return error.message;
+  }
+  if (typeof error === 'string') {
+    return error;
+  }
+  return fallback;
+}
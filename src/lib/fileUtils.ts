@@ -186,6 +186,20 @@ export function getDirectory(path: string): string {
   return parts.join('/') || '/';
 }
 
+// Archives the explorer can browse like folders
+const ARCHIVE_PATTERN = /\.(zip|jar|war|ear|whl|nupkg|vsix|tar|tgz|tar\.gz)$/i;
+
+// Separates an archive from a file inside it, e.g. `release.tgz!/bin/tool`
+export const ARCHIVE_SEPARATOR = '!/';
+
+export function isArchive(path: string): boolean {
+  return !path.includes(ARCHIVE_SEPARATOR) && ARCHIVE_PATTERN.test(path);
+}
+
+export function isInArchive(path: string): boolean {
+  return path.includes(ARCHIVE_SEPARATOR);
+}
+
 export function getExtension(filename: string): string {
   const lastDot = filename.lastIndexOf('.');
   return lastDot > 0 ? filename.substring(lastDot) : '';
@@ -0,0 +1,22 @@
+// URIs for the backend's vfs_* commands, which work the same on every
+// backend: file:///path for local files, sftp://<session id>/path over SSH.
+// Paths are used as they are, without percent-encoding.
+export function toUri(path: string, sessionId?: string): string {
+  if (sessionId) {
+    return `sftp://${sessionId}${path.startsWith('/') ? '' : '/'}${path}`;
+  }
+  const normalized = path.replace(/\\/g, '/');
+  return `file://${normalized.startsWith('/') ? '' : '/'}${normalized}`;
+}
+
+// The plain path a URI from the backend points to, e.g. for entries listed
+// with vfs_read_directory.
+export function uriPath(uri: string): string {
+  if (uri.startsWith('file://')) {
+    const path = uri.slice('file://'.length);
+    // file:///C:/dir names the Windows path C:/dir
+    return /^\/[A-Za-z]:/.test(path) ? path.slice(1) : path;
+  }
+  const rest = uri.slice('sftp://'.length);
+  return rest.slice(rest.indexOf('/'));
+}
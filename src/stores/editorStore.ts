@@ -2,6 +2,9 @@ import { create } from "zustand";
 import { persist, createJSONStorage } from "zustand/middleware";
 import { invoke } from "@tauri-apps/api/core";
 import type {
+  ArchiveFile,
+  DirectoryListing,
+  FileFingerprint,
   FileTab,
   TerminalInstance,
   FileEntry,
@@ -9,8 +12,20 @@ import type {
   GitBranches,
   GitCommit,
   RemoteConnection,
+  RecoveredBuffer,
+  VfsTextFile,
 } from "../types";
-import { detectLanguage, getFileName } from "../lib/fileUtils";
+import {
+  detectLanguage,
+  getFileName,
+  isArchive,
+  isInArchive,
+} from "../lib/fileUtils";
+import { isCommandError } from "../lib/errors";
+import { toUri, uriPath } from "../lib/vfs";
+
+// What to do with a buffer whose file changed on disk since it was read
+export type SaveConflictAction = "overwrite" | "reload" | "compare" | "cancel";
 
 interface EditorState {
   // File Explorer
@@ -22,6 +37,7 @@ interface EditorState {
   // Editor Tabs
   tabs: FileTab[];
   activeTabId: string | null;
+  saveConflictTabId: string | null; // Tab whose save was refused, see resolveSaveConflict
 
   // Terminal
   terminals: TerminalInstance[];
@@ -60,18 +76,23 @@ interface EditorState {
   remoteDialogVisible: boolean;
 
   // Actions - File Explorer
-  setRootPath: (path: string | null) => void;
+  setRootPath: (path: string | null) => Promise<void>;
   toggleFolder: (path: string) => Promise<void>;
   setSelectedPath: (path: string | null) => void;
   refreshFolder: (path: string) => Promise<void>;
 
   // Actions - Editor
-  openFile: (path: string) => Promise<void>;
+  // sessionId opens the file over SSH instead of from the local disk
+  openFile: (path: string, sessionId?: string) => Promise<void>;
+  restoreBufferBackups: () => Promise<void>;
   closeTab: (id: string) => void;
   closeAllTabs: () => void;
   setActiveTab: (id: string) => void;
   updateContent: (id: string, content: string) => void;
-  saveFile: (id: string) => Promise<void>;
+  // overwrite saves even if the file changed on disk since it was read
+  saveFile: (id: string, overwrite?: boolean) => Promise<void>;
+  reloadFile: (id: string) => Promise<void>;
+  resolveSaveConflict: (action: SaveConflictAction) => Promise<void>;
   saveAllFiles: () => Promise<void>;
   nextTab: () => void;
   previousTab: () => void;
@@ -111,12 +132,40 @@ interface EditorState {
   connectRemote: (id: string, password?: string) => Promise<void>;
   disconnectRemote: () => Promise<void>;
   remoteToggleFolder: (path: string) => Promise<void>;
-  remoteOpenFile: (path: string) => Promise<void>;
 }
 
 // Helper to generate unique IDs
 const generateId = () => crypto.randomUUID();
 
+// Caches a tree listing along with every subdirectory listed in full, so
+// expanding those later needs no round-trip
+const cacheListing = (
+  fileTree: Map<string, FileEntry[]>,
+  listing: DirectoryListing,
+) => {
+  fileTree.set(
+    listing.path,
+    listing.entries.map(({ children: _, error: __, ...entry }) => entry),
+  );
+  for (const { children, error } of listing.entries) {
+    if (error) {
+      // Left uncached, so expanding it tries again
+      console.error("Failed to read directory:", error);
+    } else if (children && !children.next_page) {
+      cacheListing(fileTree, children);
+    }
+  }
+};
+
+// Lists a remote folder through the vfs commands, with entry paths kept as
+// plain remote paths like the rest of the remote tree
+const readRemoteDirectory = async (sessionId: string, path: string) => {
+  const entries = await invoke<FileEntry[]>("vfs_read_directory", {
+    uri: toUri(path, sessionId),
+  });
+  return entries.map((entry) => ({ ...entry, path: uriPath(entry.path) }));
+};
+
 export const useEditorStore = create<EditorState>()(
   persist(
     (set, get) => ({
@@ -128,6 +177,7 @@ export const useEditorStore = create<EditorState>()(
 
       tabs: [],
       activeTabId: null,
+      saveConflictTabId: null,
 
       terminals: [],
       activeTerminalId: null,
@@ -163,7 +213,23 @@ export const useEditorStore = create<EditorState>()(
       remoteDialogVisible: false,
 
       // File Explorer Actions
-      setRootPath: (path) => {
+      setRootPath: async (path) => {
+        const previous = get().rootPath;
+        if (previous && previous !== path) {
+          await invoke("close_workspace_root", { path: previous }).catch(
+            console.error,
+          );
+        }
+        // The backend refuses paths outside the open workspace
+        if (path) {
+          try {
+            await invoke("open_workspace_root", { path });
+          } catch (error) {
+            console.error("Failed to open workspace:", error);
+            return;
+          }
+        }
+
         set({
           rootPath: path,
           expandedFolders: new Set(),
@@ -172,6 +238,7 @@ export const useEditorStore = create<EditorState>()(
         });
         if (path) {
           get().toggleFolder(path);
+          get().restoreBufferBackups();
         }
       },
 
@@ -184,15 +251,49 @@ export const useEditorStore = create<EditorState>()(
         } else {
           newExpanded.add(path);
 
-          // Load folder contents if not cached
-          if (!fileTree.has(path)) {
+          // Load folder contents if not cached. Archives are listed one
+          // folder at a time, directories along with their subfolders.
+          const archive = isArchive(path) || isInArchive(path);
+          if (!fileTree.has(path) && archive) {
             try {
-              const entries = await invoke<FileEntry[]>("read_directory", {
-                path,
-              });
-              const newFileTree = new Map(fileTree);
+              const entries = await invoke<FileEntry[]>(
+                "read_archive_directory",
+                { path },
+              );
+              const newFileTree = new Map(get().fileTree);
               newFileTree.set(path, entries);
               set({ fileTree: newFileTree });
+            } catch (error) {
+              console.error("Failed to read archive:", error);
+            }
+          } else if (!fileTree.has(path)) {
+            try {
+              const listing = await invoke<DirectoryListing>(
+                "read_directory_tree",
+                { path, depth: 2 },
+              );
+              const newFileTree = new Map(get().fileTree);
+              cacheListing(newFileTree, listing);
+              set({ fileTree: newFileTree });
+
+              // Huge folders come in pages, so the first entries show right away
+              let nextPage = listing.next_page;
+              while (nextPage) {
+                const more = await invoke<DirectoryListing>(
+                  "read_directory_tree",
+                  { path, depth: 1, page: nextPage },
+                );
+                const current = get().fileTree;
+                const updated = new Map(current);
+                updated.set(path, [
+                  ...(current.get(path) ?? []),
+                  ...more.entries.map(
+                    ({ children: _, error: __, ...entry }) => entry,
+                  ),
+                ]);
+                set({ fileTree: updated });
+                nextPage = more.next_page;
+              }
             } catch (error) {
               console.error("Failed to read directory:", error);
             }
@@ -217,18 +318,30 @@ export const useEditorStore = create<EditorState>()(
       },
 
       // Editor Actions
-      openFile: async (path) => {
+      openFile: async (path, sessionId) => {
         const { tabs } = get();
 
         // Check if file is already open
-        const existingTab = tabs.find((t) => t.path === path);
+        const existingTab = tabs.find(
+          (t) => t.path === path && t.remote?.sessionId === sessionId,
+        );
         if (existingTab) {
           set({ activeTabId: existingTab.id });
           return;
         }
 
         try {
-          const content = await invoke<string>("read_file_content", { path });
+          // Local and remote files are read the same way, through the
+          // backend's providers; only archive members are read apart
+          const readOnly = !sessionId && isInArchive(path);
+          const file = readOnly
+            ? await invoke<ArchiveFile>("read_archive_file", { path })
+            : await invoke<VfsTextFile>("vfs_read_text_file", {
+                uri: toUri(path, sessionId),
+              });
+          const { content, encoding, has_bom, line_ending } = file;
+          const fingerprint =
+            "fingerprint" in file ? file.fingerprint : undefined;
           const name = getFileName(path);
           const language = detectLanguage(name);
 
@@ -240,6 +353,12 @@ export const useEditorStore = create<EditorState>()(
             content,
             originalContent: content,
             isDirty: false,
+            encoding,
+            hasBom: has_bom,
+            lineEnding: line_ending,
+            readOnly,
+            fingerprint,
+            remote: sessionId ? { sessionId } : undefined,
           };
 
           set((state) => ({
@@ -252,6 +371,49 @@ export const useEditorStore = create<EditorState>()(
         }
       },
 
+      // Reopens buffers left unsaved when the app last closed, as dirty tabs
+      // compared against what is on disk now
+      restoreBufferBackups: async () => {
+        const { rootPath } = get();
+        if (!rootPath) return;
+
+        let backups: RecoveredBuffer[];
+        try {
+          backups = await invoke<RecoveredBuffer[]>("list_buffer_backups", {
+            workspaceRoot: rootPath,
+          });
+        } catch (error) {
+          console.error("Failed to list buffer backups:", error);
+          return;
+        }
+
+        const open = new Set(get().tabs.map((t) => t.path));
+        const restored: FileTab[] = backups
+          .filter((backup) => !open.has(backup.path))
+          .map((backup) => {
+            const name = getFileName(backup.path);
+            return {
+              id: generateId(),
+              path: backup.path,
+              name,
+              language: detectLanguage(name),
+              content: backup.content,
+              originalContent: backup.disk?.content ?? "",
+              isDirty: true,
+              encoding: backup.encoding ?? backup.disk?.encoding,
+              hasBom: backup.has_bom ?? backup.disk?.has_bom,
+              lineEnding: backup.line_ending ?? backup.disk?.line_ending,
+              fingerprint: backup.disk?.fingerprint,
+            };
+          });
+        if (restored.length === 0) return;
+
+        set((state) => ({
+          tabs: [...state.tabs, ...restored],
+          activeTabId: state.activeTabId ?? restored[0].id,
+        }));
+      },
+
       closeTab: (id) => {
         const { tabs, activeTabId } = get();
         const tabIndex = tabs.findIndex((t) => t.id === id);
@@ -288,39 +450,118 @@ export const useEditorStore = create<EditorState>()(
         }));
       },
 
-      saveFile: async (id) => {
+      saveFile: async (id, overwrite = false) => {
         const { tabs } = get();
         const tab = tabs.find((t) => t.id === id);
 
-        if (!tab || !tab.isDirty) return;
+        if (!tab || !tab.isDirty || tab.readOnly) return;
 
         try {
-          // Check if this is a remote file
-          if (tab.remote) {
-            await invoke("ssh_write_file", {
-              id: tab.remote.sessionId,
-              path: tab.path,
-              content: tab.content,
-            });
-          } else {
-            await invoke("write_file_content", {
-              path: tab.path,
+          const fingerprint = await invoke<FileFingerprint>(
+            "vfs_write_text_file",
+            {
+              uri: toUri(tab.path, tab.remote?.sessionId),
               content: tab.content,
-            });
-          }
+              // Refuses to overwrite changes made to the file since it was read
+              expectedFingerprint: overwrite ? null : (tab.fingerprint ?? null),
+              encoding: tab.encoding,
+              withBom: tab.hasBom,
+              // Keep the file's line endings whatever the editor emits
+              format: tab.lineEnding ? { line_ending: tab.lineEnding } : null,
+            },
+          );
 
           set((state) => ({
             tabs: state.tabs.map((t) =>
               t.id === id
-                ? { ...t, isDirty: false, originalContent: t.content }
+                ? {
+                    ...t,
+                    isDirty: false,
+                    originalContent: t.content,
+                    fingerprint,
+                    diskContent: undefined,
+                  }
                 : t,
             ),
           }));
         } catch (error) {
+          if (isCommandError(error) && error.kind === "modified_on_disk") {
+            set({ saveConflictTabId: id });
+            return;
+          }
           console.error("Failed to save file:", error);
         }
       },
 
+      // Replaces the buffer with the file as it is on disk now
+      reloadFile: async (id) => {
+        const tab = get().tabs.find((t) => t.id === id);
+        if (!tab) return;
+
+        try {
+          const file = await invoke<VfsTextFile>("vfs_read_text_file", {
+            uri: toUri(tab.path, tab.remote?.sessionId),
+          });
+          set((state) => ({
+            tabs: state.tabs.map((t) =>
+              t.id === id
+                ? {
+                    ...t,
+                    content: file.content,
+                    originalContent: file.content,
+                    isDirty: false,
+                    encoding: file.encoding,
+                    hasBom: file.has_bom,
+                    lineEnding: file.line_ending,
+                    fingerprint: file.fingerprint,
+                    diskContent: undefined,
+                  }
+                : t,
+            ),
+          }));
+        } catch (error) {
+          console.error("Failed to reload file:", error);
+        }
+      },
+
+      resolveSaveConflict: async (action) => {
+        const id = get().saveConflictTabId;
+        set({ saveConflictTabId: null });
+        if (!id) return;
+
+        if (action === "overwrite") {
+          await get().saveFile(id, true);
+        } else if (action === "reload") {
+          await get().reloadFile(id);
+        } else if (action === "compare") {
+          const tab = get().tabs.find((t) => t.id === id);
+          if (!tab) return;
+          try {
+            // Having seen the version on disk, the next save replaces it
+            const file = await invoke<VfsTextFile>("vfs_read_text_file", {
+              uri: toUri(tab.path, tab.remote?.sessionId),
+            });
+            set((state) => ({
+              activeTabId: id,
+              tabs: state.tabs.map((t) =>
+                t.id === id
+                  ? {
+                      ...t,
+                      originalContent: file.content,
+                      isDirty: t.content !== file.content,
+                      fingerprint: file.fingerprint,
+                      diskContent: file.content,
+                    }
+                  : t,
+              ),
+            }));
+          } catch (error) {
+            // Deleted on disk: there is nothing to compare with
+            console.error("Failed to read file for comparison:", error);
+          }
+        }
+      },
+
       saveAllFiles: async () => {
         const { tabs, saveFile } = get();
         const dirtyTabs = tabs.filter((t) => t.isDirty);
@@ -559,10 +800,7 @@ export const useEditorStore = create<EditorState>()(
           });
 
           // Load initial directory
-          const entries = await invoke<FileEntry[]>("ssh_list_directory", {
-            id: sessionId,
-            path: homePath,
-          });
+          const entries = await readRemoteDirectory(sessionId, homePath);
 
           const newFileTree = new Map<string, FileEntry[]>();
           newFileTree.set(homePath, entries);
@@ -620,10 +858,7 @@ export const useEditorStore = create<EditorState>()(
           // Load folder contents if not cached
           if (!remoteFileTree.has(path)) {
             try {
-              const entries = await invoke<FileEntry[]>("ssh_list_directory", {
-                id: activeRemoteId,
-                path,
-              });
+              const entries = await readRemoteDirectory(activeRemoteId, path);
               const newTree = new Map(remoteFileTree);
               newTree.set(path, entries);
               set({ remoteFileTree: newTree });
@@ -635,47 +870,6 @@ export const useEditorStore = create<EditorState>()(
 
         set({ remoteExpandedFolders: newExpanded });
       },
-
-      remoteOpenFile: async (path) => {
-        const { activeRemoteId, tabs } = get();
-        if (!activeRemoteId) return;
-
-        // Check if file is already open
-        const existingTab = tabs.find(
-          (t) => t.path === path && t.remote?.sessionId === activeRemoteId,
-        );
-        if (existingTab) {
-          set({ activeTabId: existingTab.id });
-          return;
-        }
-
-        try {
-          const content = await invoke<string>("ssh_read_file", {
-            id: activeRemoteId,
-            path,
-          });
-          const name = getFileName(path);
-          const language = detectLanguage(name);
-
-          const newTab: FileTab = {
-            id: generateId(),
-            path,
-            name,
-            language,
-            content,
-            originalContent: content,
-            isDirty: false,
-            remote: { sessionId: activeRemoteId },
-          };
-
-          set((state) => ({
-            tabs: [...state.tabs, newTab],
-            activeTabId: newTab.id,
-          }));
-        } catch (error) {
-          console.error("Failed to open remote file:", error);
-        }
-      },
     }),
     {
       name: "lite-editor-storage",
@@ -5,12 +5,34 @@ export interface FileEntry {
   is_directory: boolean;
   is_symlink: boolean;
   size: number;
+  is_ignored: boolean;
+}
+
+// Result of read_directory_tree; directories past the entry cap are paged
+export interface DirectoryListing {
+  path: string;
+  entries: TreeEntry[];
+  total: number;
+  next_page: string | null;
+}
+
+export interface TreeEntry extends FileEntry {
+  children: DirectoryListing | null; // Loaded for subdirectories within depth
+  error: CommandError | null; // Set when a subdirectory within depth couldn't be read
 }
 
 export interface FileInfo {
   size: number;
   is_binary: boolean;
   is_readonly: boolean;
+  mtime_ms: number | null;
+  ctime_ms: number | null;
+  mode: number | null;
+  owner: string | null;
+  symlink_target: string | null;
+  mime: string | null;
+  encoding: string | null;
+  line_ending: LineEnding | null;
 }
 
 // Remote connection types
@@ -37,9 +59,140 @@ export interface FileTab {
   content: string;
   originalContent: string;
   isDirty: boolean;
+  encoding?: string; // Encoding detected on open, kept when saving
+  hasBom?: boolean;
+  lineEnding?: LineEnding | null;
+  readOnly?: boolean; // Files inside archives can't be saved
+  fingerprint?: FileFingerprint; // Version on disk the buffer was read from, checked on save
+  diskContent?: string; // Set while comparing the buffer with a file changed on disk
   remote?: RemoteSession; // If set, file is on remote server
 }
 
+export interface TextFile {
+  content: string;
+  fingerprint: FileFingerprint;
+  encoding: string;
+  has_bom: boolean;
+  line_ending: LineEnding | null;
+  has_final_newline: boolean;
+}
+
+export type LineEnding = "lf" | "crlf" | "mixed";
+
+// Text file read with vfs_read_text_file, on any backend
+export interface VfsTextFile {
+  content: string;
+  fingerprint: FileFingerprint;
+  encoding: string;
+  has_bom: boolean;
+  line_ending: LineEnding | null;
+}
+
+// File inside an archive, read with read_archive_file
+export interface ArchiveFile {
+  content: string;
+  encoding: string;
+  has_bom: boolean;
+  line_ending: LineEnding | null;
+  size: number;
+}
+
+export interface FileFingerprint {
+  mtime_ms: number;
+  size: number;
+  hash: string;
+}
+
+// Local history: earlier versions of a file, kept whenever it is saved over
+export interface HistoryEntry {
+  id: string;
+  timestamp_ms: number;
+  size: number;
+  hash: string;
+  source: "save" | "restore";
+}
+
+export interface HistorySnapshot {
+  entry: HistoryEntry;
+  content: string;
+  encoding: string;
+  has_bom: boolean;
+}
+
+// Unsaved buffer backed up by hot exit, with the file as it is on disk now
+export interface RecoveredBuffer {
+  path: string;
+  content: string;
+  encoding: string | null;
+  has_bom: boolean | null;
+  line_ending: LineEnding | null;
+  saved_at_ms: number;
+  disk: TextFile | null; // null if the file was deleted
+}
+
+// One side of compare_texts: a file on disk, or text such as an unsaved buffer
+export type DiffSide = { path: string } | { text: string };
+
+export interface DiffOptions {
+  algorithm?: "myers" | "patience";
+  context_lines?: number | null; // null returns the whole file as one hunk
+}
+
+export interface InlineChange {
+  column: number; // 1-based
+  length: number;
+}
+
+export interface DiffLine {
+  kind: "context" | "removed" | "added";
+  old_line: number | null;
+  new_line: number | null;
+  text: string;
+  changes: InlineChange[];
+}
+
+export interface DiffHunk {
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+}
+
+export interface FileDiff {
+  hunks: DiffHunk[];
+  added: number;
+  removed: number;
+  identical: boolean;
+}
+
+// Error returned by every backend command
+export type ErrorKind =
+  | "not_found"
+  | "already_exists"
+  | "permission_denied"
+  | "outside_workspace"
+  | "read_only"
+  | "modified_on_disk"
+  | "not_utf8"
+  | "binary"
+  | "too_large"
+  | "unencodable"
+  | "invalid_input"
+  | "session_not_found"
+  | "session_lost"
+  | "git"
+  | "io";
+
+export interface CommandError {
+  kind: ErrorKind;
+  message: string;
+  path: string | null;
+  os_code: number | null;
+  retryable: boolean;
+  current?: FileFingerprint; // modified_on_disk: the file as it is now, absent if deleted
+}
+
 // Terminal types
 export interface TerminalInstance {
   id: string;
@@ -77,6 +230,62 @@ export interface QuickOpenItem {
   relativePath: string;
 }
 
+export interface FileMatch {
+  path: string;
+  relative_path: string;
+  score: number;
+  positions: number[];
+}
+
+export interface FileQueryResult {
+  matches: FileMatch[];
+  indexing: boolean;
+}
+
+// Large file mode types
+export interface LargeFileInfo {
+  path: string;
+  size: number;
+  encoding: string;
+}
+
+export interface LargeFileProgress {
+  path: string;
+  bytes_indexed: number;
+  line_count: number;
+  done: boolean;
+  error: CommandError | null; // Indexing failed; lines found so far stay readable
+}
+
+export interface LineRange {
+  start_line: number;
+  lines: string[];
+  truncated_lines: number[];
+  line_count: number;
+  indexing: boolean;
+  index_error: CommandError | null;
+}
+
+// Hex viewer types
+export interface ByteRange {
+  offset: number;
+  data: string; // Base64
+  file_size: number;
+}
+
+export interface BytePatch {
+  offset: number;
+  data: string; // Base64
+}
+
+export interface ImagePreview {
+  mime: string;
+  data_url: string;
+  width: number | null;
+  height: number | null;
+  size: number;
+}
+
 // Git types
 export interface GitFileStatus {
   path: string;